@@ -0,0 +1,191 @@
+//! Interface of the validator voting contract for other contracts. Unlike the contract crate, it
+//! exports no contract methods, so contracts can depend on it and call the voting contract with
+//! the typed `ext_validator_voting` bindings, or implement `ProposalSubscriber` to be notified of
+//! the outcome of proposals.
+
+use near_sdk::json_types::U128;
+use near_sdk::{ext_contract, near, require, EpochHeight};
+
+/// Balance in yocto NEAR
+type Balance = u128;
+/// Timestamp in milliseconds
+type Timestamp = u64;
+/// Identifier of a proposal in the registry
+pub type ProposalId = u64;
+
+/// Name of the interface other contracts can rely on to read the outcome of proposals.
+pub const VOTING_STANDARD: &str = "validator-voting";
+/// Version of the interface. Bumped on breaking changes of `ValidatorVoting`.
+pub const VOTING_STANDARD_VERSION: &str = "1.0.0";
+
+#[near(serializers = [borsh, json])]
+#[serde(rename_all = "lowercase")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Vote {
+    Yes,
+    No,
+    Abstain,
+}
+
+/// A fraction of the total stake, `numerator / denominator`.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ratio {
+    pub numerator: u32,
+    pub denominator: u32,
+}
+
+impl Ratio {
+    /// Returns `amount * numerator / denominator` rounded down, without overflowing.
+    pub fn apply(&self, amount: Balance) -> Balance {
+        let numerator = self.numerator as Balance;
+        let denominator = self.denominator as Balance;
+        amount / denominator * numerator + amount % denominator * numerator / denominator
+    }
+
+    /// Returns `1 - self`.
+    pub fn complement(&self) -> Ratio {
+        Ratio {
+            numerator: self.denominator - self.numerator,
+            denominator: self.denominator,
+        }
+    }
+}
+
+/// The outcome of a proposal once the voting has ended.
+#[near(serializers = [borsh, json])]
+#[serde(rename_all = "lowercase")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Outcome {
+    Approved,
+    Rejected,
+    Cancelled,
+    /// The deadline passed without an approval or rejection, recorded by `finalize`.
+    Expired,
+}
+
+/// The state of a proposal.
+#[near(serializers = [borsh, json])]
+#[serde(rename_all = "lowercase")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ProposalStatus {
+    /// Voting has not started yet.
+    Pending,
+    /// Voting is open.
+    Active,
+    /// The approval threshold was reached.
+    Approved,
+    /// The approval threshold can no longer be reached.
+    Rejected,
+    /// The deadline has passed without an outcome.
+    Expired,
+    /// The proposal was withdrawn before an outcome was reached.
+    Cancelled,
+}
+
+impl From<Outcome> for ProposalStatus {
+    fn from(outcome: Outcome) -> Self {
+        match outcome {
+            Outcome::Approved => ProposalStatus::Approved,
+            Outcome::Rejected => ProposalStatus::Rejected,
+            Outcome::Cancelled => ProposalStatus::Cancelled,
+            Outcome::Expired => ProposalStatus::Expired,
+        }
+    }
+}
+
+/// Sum of the stake voted for each choice.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Tally {
+    pub yes: U128,
+    pub no: U128,
+    pub abstain: U128,
+}
+
+impl Tally {
+    fn stake_mut(&mut self, vote: &Vote) -> &mut U128 {
+        match vote {
+            Vote::Yes => &mut self.yes,
+            Vote::No => &mut self.no,
+            Vote::Abstain => &mut self.abstain,
+        }
+    }
+
+    /// Total stake that took part in the vote.
+    pub fn total(&self) -> Balance {
+        self.yes.0 + self.no.0 + self.abstain.0
+    }
+
+    pub fn add(&mut self, vote: &Vote, stake: Balance) {
+        self.stake_mut(vote).0 += stake;
+    }
+
+    pub fn sub(&mut self, vote: &Vote, stake: Balance) {
+        let voted_stake = self.stake_mut(vote);
+        require!(
+            stake <= voted_stake.0,
+            format!(
+                "invariant: voted stake {} is more than total voted stake {}",
+                stake, voted_stake.0
+            )
+        );
+        voted_stake.0 -= stake;
+    }
+}
+
+/// Describes the interface implemented by the contract and its voting rules.
+#[near(serializers = [json])]
+#[derive(Clone, Debug, PartialEq)]
+pub struct VotingMetadata {
+    pub standard: String,
+    pub version: String,
+    pub threshold: Ratio,
+    pub quorum: Option<Ratio>,
+}
+
+/// The votes of a proposal when the voting ended, stored so it doesn't change with later stake.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, PartialEq)]
+pub struct FinalTally {
+    pub outcome: Outcome,
+    /// Stake that voted for the outcome, `No` for rejected proposals and `Yes` otherwise.
+    pub voted_stake: U128,
+    pub tally: Tally,
+    /// Total stake of the validators the outcome was decided with. For proposals that expired or
+    /// were cancelled, the total stake of the epoch the voting ended in, which is later than
+    /// `epoch_height` if the proposal wasn't pinged since.
+    pub total_stake: U128,
+    pub threshold: Ratio,
+    pub num_votes: u64,
+    /// Epoch of the stake the tally was counted with, the last completed `ping`.
+    pub epoch_height: EpochHeight,
+    pub timestamp_ms: Timestamp,
+}
+
+/// Stable interface for other contracts to read the outcome of proposals. Downstream contracts
+/// can depend on this crate and call it with `ext_validator_voting`.
+#[ext_contract(ext_validator_voting)]
+pub trait ValidatorVoting {
+    /// Whether the proposal was approved.
+    fn is_approved(&self, proposal_id: ProposalId) -> bool;
+
+    /// Returns the votes of the proposal once the voting has ended, `None` before. Also `None` for
+    /// proposals that ended before the contract stored final tallies.
+    fn get_final_tally(&self, proposal_id: ProposalId) -> Option<FinalTally>;
+
+    /// Returns the name and version of the interface and the voting rules.
+    fn voting_metadata(&self) -> VotingMetadata;
+}
+
+/// Interface of the contracts notified when a proposal is approved or rejected.
+#[ext_contract(ext_subscriber)]
+pub trait ProposalSubscriber {
+    fn on_proposal_finalized(
+        &mut self,
+        proposal_id: ProposalId,
+        status: ProposalStatus,
+        voted_stake: U128,
+        total_stake: U128,
+    );
+}
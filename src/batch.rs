@@ -0,0 +1,146 @@
+use crate::*;
+use near_sdk::serde_json;
+use near_sdk::PromiseResult;
+use std::collections::HashSet;
+
+/// Maximum number of staking pools in a single `vote_batch` call.
+pub const MAX_BATCH_SIZE: usize = 10;
+
+/// The result of the vote for a single staking pool in `vote_batch`.
+#[near(serializers = [json])]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PoolVoteResult {
+    pub staking_pool_id: AccountId,
+    /// Why the vote was not recorded. `None` if the vote was recorded.
+    pub error: Option<String>,
+}
+
+/// Batch voting methods
+#[near]
+impl Contract {
+    /// Vote with the same choice for several staking pools owned by, or delegated to, the
+    /// caller. The owner of each pool is checked, and every pool that passes the checks votes
+    /// in a single callback. Returns whether the vote of each pool was recorded.
+    pub fn vote_batch(
+        &mut self,
+        proposal_id: ProposalId,
+        vote: Vote,
+        staking_pool_ids: Vec<AccountId>,
+    ) -> Promise {
+        require!(!staking_pool_ids.is_empty(), "No staking pools to vote for");
+        require!(
+            staking_pool_ids.len() <= MAX_BATCH_SIZE,
+            format!("Cannot vote for more than {} staking pools", MAX_BATCH_SIZE)
+        );
+        require!(
+            staking_pool_ids.iter().collect::<HashSet<_>>().len() == staking_pool_ids.len(),
+            "Duplicate staking pools"
+        );
+        let proposal = self.internal_get_proposal(proposal_id);
+        proposal.assert_active();
+        require!(
+            !proposal.is_commit_reveal(),
+            "Votes must be committed and revealed for this proposal"
+        );
+
+        let get_owner_ids = staking_pool_ids
+            .iter()
+            .map(|staking_pool_id| {
+                ext_staking_pool::ext(staking_pool_id.clone())
+                    .with_static_gas(GET_OWNER_ID_GAS)
+                    .get_owner_id()
+            })
+            .reduce(|joint, promise| joint.and(promise))
+            .unwrap();
+        get_owner_ids.then(Self::ext(env::current_account_id()).on_get_pool_owner_ids(
+            env::predecessor_account_id(),
+            proposal_id,
+            staking_pool_ids,
+            vote,
+        ))
+    }
+
+    /// Check the owner id of each pool and vote for those the voter is allowed to vote for.
+    #[private]
+    pub fn on_get_pool_owner_ids(
+        &mut self,
+        voter_account_id: AccountId,
+        proposal_id: ProposalId,
+        staking_pool_ids: Vec<AccountId>,
+        vote: Vote,
+    ) -> Vec<PoolVoteResult> {
+        require!(
+            env::promise_results_count() == staking_pool_ids.len() as u64,
+            "Unexpected number of promise results"
+        );
+        // refresh the stake once for the whole batch, so the votes don't each ping
+        if self
+            .internal_get_proposal(proposal_id)
+            .check_active()
+            .is_ok()
+        {
+            self.internal_update_proposal(proposal_id, |proposal, config| {
+                proposal.ping(proposal_id, config, DEFAULT_PING_LIMIT)
+            });
+        }
+        staking_pool_ids
+            .into_iter()
+            .enumerate()
+            .map(|(index, staking_pool_id)| {
+                let error = self
+                    .internal_check_batch_vote(
+                        index as u64,
+                        proposal_id,
+                        &staking_pool_id,
+                        &voter_account_id,
+                    )
+                    .err();
+                if error.is_none() {
+                    self.internal_vote(
+                        proposal_id,
+                        vote,
+                        staking_pool_id.clone(),
+                        voter_account_id.clone(),
+                        0,
+                    );
+                }
+                PoolVoteResult {
+                    staking_pool_id,
+                    error,
+                }
+            })
+            .collect()
+    }
+}
+
+impl Contract {
+    /// Checks, without panicking, that the vote for the staking pool can be recorded.
+    fn internal_check_batch_vote(
+        &self,
+        result_index: u64,
+        proposal_id: ProposalId,
+        staking_pool_id: &AccountId,
+        voter_account_id: &AccountId,
+    ) -> Result<(), String> {
+        let actual_owner_id = match env::promise_result(result_index) {
+            PromiseResult::Successful(value) => serde_json::from_slice::<AccountId>(&value)
+                .map_err(|_| "Failed to get the staking pool owner id".to_string())?,
+            PromiseResult::Failed => {
+                return Err("Failed to get the staking pool owner id".to_string())
+            }
+        };
+        if !self.is_allowed_voter(staking_pool_id, &actual_owner_id, voter_account_id) {
+            return Err(
+                "Voting is only allowed for the staking pool owner or its voter".to_string(),
+            );
+        }
+        // the voting may have ended since the batch was submitted, or by an earlier vote of it
+        self.internal_get_proposal(proposal_id)
+            .check_active()
+            .map_err(str::to_string)?;
+        if validator_stake(staking_pool_id) == 0 {
+            return Err(format!("{} is not a validator", staking_pool_id));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+use crate::*;
+
+/// Commit-reveal voting methods
+#[near]
+impl Contract {
+    /// Commit a hidden vote for the staking pool during the commit phase of the proposal.
+    /// `commitment` is the hex encoded sha256 hash of the vote as `yes`, `no` or `abstain`
+    /// followed by a secret salt. Can be called by the same accounts as `vote`.
+    pub fn commit_vote(
+        &mut self,
+        proposal_id: ProposalId,
+        staking_pool_id: AccountId,
+        commitment: String,
+    ) -> PromiseOrValue<()> {
+        if env::predecessor_account_id() == staking_pool_id {
+            self.internal_get_proposal_mut(proposal_id).commit(
+                proposal_id,
+                staking_pool_id.clone(),
+                staking_pool_id,
+                commitment,
+            );
+            return PromiseOrValue::Value(());
+        }
+        ext_staking_pool::ext(staking_pool_id.clone())
+            .with_static_gas(GET_OWNER_ID_GAS)
+            .get_owner_id()
+            .then(
+                Self::ext(env::current_account_id()).on_get_pool_owner_id_for_commit(
+                    env::predecessor_account_id(),
+                    proposal_id,
+                    staking_pool_id,
+                    commitment,
+                ),
+            )
+            .into()
+    }
+
+    /// Check the pool owner id and commit the vote.
+    #[private]
+    pub fn on_get_pool_owner_id_for_commit(
+        &mut self,
+        voter_account_id: AccountId,
+        proposal_id: ProposalId,
+        staking_pool_id: AccountId,
+        commitment: String,
+        #[callback_result] pool_owner_id_result: Result<AccountId, PromiseError>,
+    ) {
+        let Ok(actual_owner_id) = pool_owner_id_result else {
+            env::panic_str("Failed to get the staking pool owner id");
+        };
+        require!(
+            self.is_allowed_voter(&staking_pool_id, &actual_owner_id, &voter_account_id),
+            "Voting is only allowed for the staking pool owner or its voter"
+        );
+        self.internal_get_proposal_mut(proposal_id).commit(
+            proposal_id,
+            staking_pool_id,
+            voter_account_id,
+            commitment,
+        );
+    }
+
+    /// Reveal the vote committed for the staking pool once the commit phase has ended. Anyone
+    /// who knows the vote and the salt can reveal it.
+    pub fn reveal_vote(
+        &mut self,
+        proposal_id: ProposalId,
+        staking_pool_id: AccountId,
+        vote: Vote,
+        salt: String,
+    ) {
+        self.internal_update_proposal(proposal_id, |proposal, config| {
+            proposal.reveal(proposal_id, config, staking_pool_id, vote, salt)
+        });
+    }
+
+    /// Returns the vote committed for the staking pool that has not been revealed yet, if any.
+    pub fn get_commitment(
+        &self,
+        proposal_id: ProposalId,
+        staking_pool_id: AccountId,
+    ) -> Option<Commitment> {
+        self.internal_get_proposal(proposal_id)
+            .commitments
+            .get(&staking_pool_id)
+            .cloned()
+    }
+}
@@ -0,0 +1,55 @@
+use crate::*;
+
+/// The 2/3 supermajority used when no threshold is given at init.
+pub const DEFAULT_THRESHOLD: Ratio = Ratio {
+    numerator: 2,
+    denominator: 3,
+};
+
+/// Voting rules applied to all proposals.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    /// A proposal is approved once more than this share of the total stake votes `Yes`.
+    pub threshold: Ratio,
+    /// Minimum share of the total stake that must vote, with any choice, for a proposal to be
+    /// approved. Greater than the threshold, so it requires votes beyond the approving ones.
+    pub quorum: Option<Ratio>,
+}
+
+impl Config {
+    pub fn new(threshold: Option<Ratio>, quorum: Option<Ratio>) -> Self {
+        let threshold = threshold.unwrap_or(DEFAULT_THRESHOLD);
+        require!(
+            threshold.numerator > 0 && threshold.numerator < threshold.denominator,
+            "Threshold must be between 0 and 1"
+        );
+        if let Some(quorum) = quorum {
+            require!(
+                quorum.numerator > 0 && quorum.numerator <= quorum.denominator,
+                "Quorum must be between 0 and 1"
+            );
+            // a quorum up to the threshold is already met by the `Yes` votes approving a proposal
+            require!(
+                quorum.numerator as u64 * threshold.denominator as u64
+                    > threshold.numerator as u64 * quorum.denominator as u64,
+                "Quorum must be greater than the threshold"
+            );
+        }
+        Self { threshold, quorum }
+    }
+
+    /// Whether the tally approves the proposal.
+    pub fn is_approved(&self, tally: &Tally, total_stake: Balance) -> bool {
+        tally.yes.0 > self.threshold.apply(total_stake)
+            && self
+                .quorum
+                .is_none_or(|quorum| tally.total() >= quorum.apply(total_stake))
+    }
+
+    /// Whether enough stake votes `No` that the proposal can no longer be approved, i.e. the
+    /// stake left for `Yes` is no longer above the threshold.
+    pub fn is_rejected(&self, tally: &Tally, total_stake: Balance) -> bool {
+        total_stake.saturating_sub(tally.no.0) <= self.threshold.apply(total_stake)
+    }
+}
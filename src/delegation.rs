@@ -0,0 +1,116 @@
+use crate::*;
+
+/// A voter registered by the owner of a staking pool to vote on behalf of the pool.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Delegation {
+    pub voter_id: AccountId,
+    /// Owner of the staking pool when the voter was registered. The voter can no longer vote
+    /// once the owner of the pool changes.
+    pub owner_id: AccountId,
+}
+
+/// Delegation methods
+#[near]
+impl Contract {
+    /// Register `voter_id` to vote on behalf of the staking pool, replacing the previous voter.
+    /// The method is called by the staking pool owner. Only validators can register a voter, which
+    /// bounds the storage paid by the voting contract to one entry per validator.
+    pub fn set_voter(&mut self, staking_pool_id: AccountId, voter_id: AccountId) -> Promise {
+        require!(
+            validator_stake(&staking_pool_id) > 0,
+            format!("{} is not a validator", staking_pool_id)
+        );
+        self.internal_check_pool_owner(staking_pool_id, Some(voter_id))
+    }
+
+    /// Revoke the voter of the staking pool. The method is called by the staking pool owner.
+    pub fn remove_voter(&mut self, staking_pool_id: AccountId) -> Promise {
+        self.internal_check_pool_owner(staking_pool_id, None)
+    }
+
+    /// Check the pool owner id and update the voter of the staking pool.
+    #[private]
+    pub fn on_get_pool_owner_id_for_voter(
+        &mut self,
+        pool_owner_id: AccountId,
+        staking_pool_id: AccountId,
+        voter_id: Option<AccountId>,
+        #[callback_result] pool_owner_id_result: Result<AccountId, PromiseError>,
+    ) {
+        let Ok(actual_owner_id) = pool_owner_id_result else {
+            env::panic_str("Failed to get the staking pool owner id");
+        };
+        require!(
+            pool_owner_id == actual_owner_id,
+            "Only the staking pool owner can change its voter"
+        );
+        match voter_id {
+            Some(voter_id) => {
+                Event::VoterSet {
+                    staking_pool_id: &staking_pool_id,
+                    owner_id: &pool_owner_id,
+                    voter_id: &voter_id,
+                }
+                .emit();
+                self.voters.insert(
+                    staking_pool_id,
+                    Delegation {
+                        voter_id,
+                        owner_id: pool_owner_id,
+                    },
+                );
+            }
+            None => {
+                let delegation = self
+                    .voters
+                    .remove(&staking_pool_id)
+                    .unwrap_or_else(|| env::panic_str("The staking pool has no voter"));
+                Event::VoterRemoved {
+                    staking_pool_id: &staking_pool_id,
+                    owner_id: &pool_owner_id,
+                    voter_id: &delegation.voter_id,
+                }
+                .emit();
+            }
+        }
+    }
+
+    /// Returns the voter registered for the staking pool, if any. The voter can only vote while
+    /// the staking pool is owned by the account that registered it.
+    pub fn get_voter(&self, staking_pool_id: AccountId) -> Option<Delegation> {
+        self.voters.get(&staking_pool_id).cloned()
+    }
+}
+
+impl Contract {
+    fn internal_check_pool_owner(
+        &mut self,
+        staking_pool_id: AccountId,
+        voter_id: Option<AccountId>,
+    ) -> Promise {
+        ext_staking_pool::ext(staking_pool_id.clone())
+            .with_static_gas(GET_OWNER_ID_GAS)
+            .get_owner_id()
+            .then(
+                Self::ext(env::current_account_id()).on_get_pool_owner_id_for_voter(
+                    env::predecessor_account_id(),
+                    staking_pool_id,
+                    voter_id,
+                ),
+            )
+    }
+
+    /// Whether the account can vote on behalf of the staking pool owned by `pool_owner_id`.
+    pub(crate) fn is_allowed_voter(
+        &self,
+        staking_pool_id: &AccountId,
+        pool_owner_id: &AccountId,
+        account_id: &AccountId,
+    ) -> bool {
+        account_id == pool_owner_id
+            || self.voters.get(staking_pool_id).is_some_and(|delegation| {
+                &delegation.voter_id == account_id && &delegation.owner_id == pool_owner_id
+            })
+    }
+}
@@ -2,10 +2,13 @@ use crate::*;
 use near_sdk::json_types::{U128, U64};
 use near_sdk::serde::Serialize;
 use near_sdk::serde_json::json;
-use near_sdk::{log, AccountId};
+use near_sdk::{log, AccountId, PublicKey};
 
 pub const EVENT_STANDARD: &str = "validator-voting";
-pub const EVENT_STANDARD_VERSION: &str = "1.0.0";
+/// Version of the event standard, bumped with each breaking change of the payloads:
+/// - 2.0.0: events carry the `proposal_id`, and `vote` is `yes`, `no` or `abstain`.
+/// - 3.0.0: deadlines of `proposal_approved` and `proposal_rejected` can be `null`.
+pub const EVENT_STANDARD_VERSION: &str = "3.0.0";
 
 #[derive(Serialize)]
 #[serde(
@@ -16,18 +19,126 @@ pub const EVENT_STANDARD_VERSION: &str = "1.0.0";
 )]
 #[must_use = "Don't forget to `.emit()` this event"]
 pub enum Event<'a> {
+    ProposalCreated {
+        proposal_id: &'a ProposalId,
+        proposal: &'a String,
+        deadline_timestamp_ms: &'a Option<U64>,
+        deadline_epoch_height: &'a Option<U64>,
+        start_timestamp_ms: &'a Option<U64>,
+        start_epoch_height: &'a Option<U64>,
+    },
     Voted {
+        proposal_id: &'a ProposalId,
         validator_id: &'a AccountId,
         vote: &'a Vote,
     },
     ProposalApproved {
+        proposal_id: &'a ProposalId,
         proposal: &'a String,
         approval_timestamp_ms: &'a U64,
-        deadline_timestamp_ms: &'a U64,
+        deadline_timestamp_ms: &'a Option<U64>,
+        deadline_epoch_height: &'a Option<U64>,
+        voted_stake: &'a U128,
+        total_stake: &'a U128,
+        num_votes: &'a U64,
+        threshold: &'a Ratio,
+        quorum: &'a Option<Ratio>,
+    },
+    ProposalRejected {
+        proposal_id: &'a ProposalId,
+        proposal: &'a String,
+        rejection_timestamp_ms: &'a U64,
+        deadline_timestamp_ms: &'a Option<U64>,
+        deadline_epoch_height: &'a Option<U64>,
         voted_stake: &'a U128,
         total_stake: &'a U128,
         num_votes: &'a U64,
     },
+    ProposalExpired {
+        proposal_id: &'a ProposalId,
+        proposal: &'a String,
+        outcome: &'a Outcome,
+        expiration_timestamp_ms: &'a U64,
+        deadline_timestamp_ms: &'a Option<U64>,
+        deadline_epoch_height: &'a Option<U64>,
+        tally: &'a Tally,
+        total_stake: &'a U128,
+        num_votes: &'a U64,
+    },
+    ProposalCancelled {
+        proposal_id: &'a ProposalId,
+        reason: &'a String,
+        cancellation_timestamp_ms: &'a U64,
+    },
+    DeadlineExtended {
+        proposal_id: &'a ProposalId,
+        old_deadline_timestamp_ms: &'a U64,
+        new_deadline_timestamp_ms: &'a U64,
+    },
+    DeadlineEpochHeightExtended {
+        proposal_id: &'a ProposalId,
+        old_deadline_epoch_height: &'a U64,
+        new_deadline_epoch_height: &'a U64,
+    },
+    OwnershipTransferStarted {
+        owner_id: &'a AccountId,
+        pending_owner_id: &'a AccountId,
+    },
+    OwnershipTransferred {
+        old_owner_id: &'a Option<AccountId>,
+        new_owner_id: &'a AccountId,
+    },
+    ProposalExecuted {
+        proposal_id: &'a ProposalId,
+        status: &'a ExecutionStatus,
+    },
+    VoteCommitted {
+        proposal_id: &'a ProposalId,
+        validator_id: &'a AccountId,
+        commitment: &'a String,
+    },
+    VoteRevealed {
+        proposal_id: &'a ProposalId,
+        validator_id: &'a AccountId,
+        vote: &'a Vote,
+    },
+    VoterSet {
+        staking_pool_id: &'a AccountId,
+        owner_id: &'a AccountId,
+        voter_id: &'a AccountId,
+    },
+    VoterRemoved {
+        staking_pool_id: &'a AccountId,
+        owner_id: &'a AccountId,
+        voter_id: &'a AccountId,
+    },
+    VoteSignerSet {
+        staking_pool_id: &'a AccountId,
+        owner_id: &'a AccountId,
+        public_key: &'a PublicKey,
+    },
+    VoteSignerRemoved {
+        staking_pool_id: &'a AccountId,
+        owner_id: &'a AccountId,
+        public_key: &'a PublicKey,
+    },
+    ContractMigrated {
+        from_version: &'a StateVersion,
+        to_version: &'a StateVersion,
+    },
+    SubscriberAdded {
+        subscriber_id: &'a AccountId,
+    },
+    SubscriberRemoved {
+        subscriber_id: &'a AccountId,
+    },
+    SubscriberNotificationFailed {
+        proposal_id: &'a ProposalId,
+        subscriber_id: &'a AccountId,
+    },
+    SubscriberNotificationDeferred {
+        proposal_id: &'a ProposalId,
+    },
 }
 
 impl Event<'_> {
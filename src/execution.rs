@@ -0,0 +1,118 @@
+use crate::*;
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::{NearToken, PromiseResult};
+
+/// Maximum total gas of the actions of a proposal, which leaves room for `execute` itself.
+pub const MAX_ACTIONS_GAS: Gas = Gas::from_tgas(250);
+const ON_EXECUTE_GAS: Gas = Gas::from_tgas(10);
+
+/// A function call made on behalf of this contract once the proposal is approved.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProposalAction {
+    pub receiver_id: AccountId,
+    pub method_name: String,
+    pub args: Base64VecU8,
+    /// Attached from the balance of this contract.
+    pub deposit: NearToken,
+    pub gas: Gas,
+}
+
+/// The state of the execution of the actions of an approved proposal.
+#[near(serializers = [borsh, json])]
+#[serde(rename_all = "lowercase")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExecutionStatus {
+    /// The actions were dispatched and their results are not known yet.
+    Pending,
+    /// All actions succeeded.
+    Succeeded,
+    /// At least one action failed. The actions are not executed again.
+    Failed,
+}
+
+/// Checks the actions when the proposal is created.
+pub fn assert_valid_actions(actions: &[ProposalAction]) {
+    let mut total_gas = Gas::from_gas(0);
+    for action in actions {
+        require!(
+            !action.method_name.is_empty(),
+            "Method name of the action cannot be empty"
+        );
+        total_gas = total_gas.saturating_add(action.gas);
+    }
+    require!(
+        total_gas <= MAX_ACTIONS_GAS,
+        format!(
+            "Total gas of the actions cannot be more than {}",
+            MAX_ACTIONS_GAS
+        )
+    );
+}
+
+/// Execution methods
+#[near]
+impl Contract {
+    /// Dispatch the actions of an approved proposal. Can be called by anyone, and only once.
+    pub fn execute(&mut self, proposal_id: ProposalId) -> Promise {
+        let proposal = self.internal_get_proposal_mut(proposal_id);
+        require!(
+            proposal.status() == ProposalStatus::Approved,
+            "Only approved proposals can be executed"
+        );
+        require!(!proposal.actions.is_empty(), "Proposal has no actions");
+        require!(
+            proposal.execution_status.is_none(),
+            "Proposal has already been executed"
+        );
+        proposal.execution_status = Some(ExecutionStatus::Pending);
+
+        proposal
+            .actions
+            .iter()
+            .map(|action| {
+                Promise::new(action.receiver_id.clone()).function_call(
+                    action.method_name.clone(),
+                    action.args.0.clone(),
+                    action.deposit,
+                    action.gas,
+                )
+            })
+            .reduce(|joint, promise| joint.and(promise))
+            .unwrap()
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(ON_EXECUTE_GAS)
+                    .on_execute(proposal_id),
+            )
+    }
+
+    /// Record the result of the actions.
+    #[private]
+    pub fn on_execute(&mut self, proposal_id: ProposalId) -> ExecutionStatus {
+        let succeeded = (0..env::promise_results_count())
+            .all(|index| matches!(env::promise_result(index), PromiseResult::Successful(_)));
+        let status = if succeeded {
+            ExecutionStatus::Succeeded
+        } else {
+            ExecutionStatus::Failed
+        };
+        self.internal_get_proposal_mut(proposal_id).execution_status = Some(status);
+        Event::ProposalExecuted {
+            proposal_id: &proposal_id,
+            status: &status,
+        }
+        .emit();
+        status
+    }
+
+    /// Returns the actions dispatched once the proposal is approved.
+    pub fn get_actions(&self, proposal_id: ProposalId) -> Vec<ProposalAction> {
+        self.internal_get_proposal(proposal_id).actions.clone()
+    }
+
+    /// Returns the state of the execution of the actions. `None` if they were not executed.
+    pub fn get_execution_status(&self, proposal_id: ProposalId) -> Option<ExecutionStatus> {
+        self.internal_get_proposal(proposal_id).execution_status
+    }
+}
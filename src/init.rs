@@ -0,0 +1,50 @@
+// the code generated for `new` takes more arguments than clippy allows
+#![allow(clippy::too_many_arguments)]
+
+use crate::*;
+
+/// Initialization
+#[near]
+impl Contract {
+    /// Initialize the contract with the first proposal, which has the id `0`.
+    /// A proposal is approved once more than `threshold` of the total stake votes `Yes`, 2/3 by
+    /// default. If `quorum` is given, at least that share of the total stake must have voted.
+    /// The optional `owner_id` can cancel proposals and extend their deadlines.
+    /// `options` are the optional settings of the first proposal. `deadline_timestamp_ms` can be
+    /// omitted if `options` set `deadline_epoch_height` instead.
+    /// `subscribers` are the contracts notified when a proposal is approved or rejected.
+    #[init]
+    #[private]
+    pub fn new(
+        proposal: String,
+        deadline_timestamp_ms: Option<Timestamp>,
+        threshold: Option<Ratio>,
+        quorum: Option<Ratio>,
+        owner_id: Option<AccountId>,
+        options: Option<ProposalOptions>,
+        subscribers: Option<Vec<AccountId>>,
+    ) -> Self {
+        let mut contract = Self {
+            version: CURRENT_STATE_VERSION,
+            owner_id,
+            pending_owner_id: None,
+            config: Config::new(threshold, quorum),
+            proposals: LookupMap::new(StorageKey::Proposals),
+            num_proposals: 0,
+            voters: LookupMap::new(StorageKey::Voters),
+            vote_signers: LookupMap::new(StorageKey::VoteSigners),
+            signed_vote_nonces: LookupMap::new(StorageKey::SignedVoteNonces),
+            subscribers: IterableSet::new(StorageKey::Subscribers),
+            pending_notifications: LookupSet::new(StorageKey::PendingNotifications),
+        };
+        for subscriber_id in subscribers.unwrap_or_default() {
+            contract.internal_add_subscriber(subscriber_id);
+        }
+        contract.internal_create_proposal(
+            proposal,
+            deadline_timestamp_ms,
+            options.unwrap_or_default(),
+        );
+        contract
+    }
+}
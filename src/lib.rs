@@ -1,27 +1,51 @@
+mod batch;
+mod commit_reveal;
+mod config;
+mod delegation;
 mod events;
+mod execution;
+mod init;
+mod metadata;
+mod migration;
+mod owner;
+mod proposal;
+mod signed_vote;
+mod standard;
+mod subscribers;
 mod utils;
 
+pub use batch::PoolVoteResult;
+pub use config::Config;
+pub use delegation::Delegation;
 use events::Event;
+use execution::assert_valid_actions;
+pub use execution::{ExecutionStatus, ProposalAction};
+pub use metadata::ProposalMetadata;
+pub use migration::StateVersion;
+use migration::{VersionedProposal, CURRENT_STATE_VERSION};
 use near_sdk::json_types::{U128, U64};
+use near_sdk::store::{IterableMap, IterableSet, LookupMap, LookupSet};
 use near_sdk::{
-    env, ext_contract, near, require, AccountId, EpochHeight, Gas, PanicOnDefault, Promise,
-    PromiseError,
+    env, ext_contract, near, require, AccountId, BorshStorageKey, EpochHeight, Gas, PanicOnDefault,
+    Promise, PromiseError, PromiseOrValue,
 };
+pub use proposal::{vote_commitment, Commitment, ProposalOptions, ProposalResult, VoteRecord};
+use proposal::{PendingPing, Proposal, DEFAULT_PING_LIMIT};
+pub use signed_vote::{nep413_nonce, SignedVote, VoteSigner};
 use std::collections::HashMap;
-use utils::{validator_stake, validator_total_stake};
+pub use subscribers::MAX_SUBSCRIBERS;
+use utils::{hex_encode, is_sha256_hex, validator_stake, validator_total_stake};
+pub use validator_voting_interface::{
+    ext_subscriber, ext_validator_voting, FinalTally, Outcome, ProposalId, ProposalStatus,
+    ProposalSubscriber, Ratio, Tally, ValidatorVoting, Vote, VotingMetadata, VOTING_STANDARD,
+    VOTING_STANDARD_VERSION,
+};
 
 /// Balance in yocto NEAR
 type Balance = u128;
 /// Timestamp in milliseconds
 type Timestamp = u64;
 
-#[near(serializers = [json])]
-#[serde(rename_all = "lowercase")]
-pub enum Vote {
-    Yes,
-    No,
-}
-
 const GET_OWNER_ID_GAS: Gas = Gas::from_tgas(5);
 
 #[ext_contract(ext_staking_pool)]
@@ -29,184 +53,347 @@ pub trait StakingPoolContract {
     fn get_owner_id(&self) -> AccountId;
 }
 
-/// Voting contract for any specific proposal. Once the majority of the stake holders agree to
-/// the proposal, the time will be recorded and the voting ends.
+#[near]
+#[derive(BorshStorageKey)]
+pub enum StorageKey {
+    Proposals,
+    Votes { proposal_id: ProposalId },
+    Voters,
+    Commitments { proposal_id: ProposalId },
+    VoteSigners,
+    SignedVoteNonces,
+    Subscribers,
+    PendingNotifications,
+}
+
+/// Voting registry for proposals. Each proposal has its own deadline and votes, and validators
+/// vote on each proposal independently.
 #[near(contract_state)]
 #[derive(PanicOnDefault)]
 pub struct Contract {
-    proposal: String,
-    deadline_timestamp_ms: Timestamp,
-    votes: HashMap<AccountId, Balance>,
-    total_voted_stake: Balance,
-    result: Option<Timestamp>,
-    last_epoch_height: EpochHeight,
+    /// Must stay the first field, so `migrate` can read it from the state of any version.
+    version: StateVersion,
+    owner_id: Option<AccountId>,
+    pending_owner_id: Option<AccountId>,
+    config: Config,
+    proposals: LookupMap<ProposalId, VersionedProposal>,
+    num_proposals: u64,
+    /// Voters registered by staking pool owners, keyed by staking pool.
+    voters: LookupMap<AccountId, Delegation>,
+    /// Keys registered by staking pool owners to sign votes, keyed by staking pool.
+    vote_signers: LookupMap<AccountId, VoteSigner>,
+    /// Nonce of the last signed vote submitted for each staking pool.
+    signed_vote_nonces: LookupMap<AccountId, u64>,
+    /// Contracts notified when a proposal is approved or rejected.
+    subscribers: IterableSet<AccountId>,
+    /// Proposals whose subscribers haven't been notified yet, for lack of gas.
+    pending_notifications: LookupSet<ProposalId>,
 }
 
 // Implement the contract structure
 #[near]
 impl Contract {
-    #[init]
+    /// Add a new proposal to the registry. Returns the id of the new proposal.
+    /// `deadline_timestamp_ms` can be omitted if `options` set `deadline_epoch_height` instead.
     #[private]
-    pub fn new(proposal: String, deadline_timestamp_ms: Timestamp) -> Self {
-        require!(!proposal.is_empty(), "Proposal cannot be empty");
-        require!(
-            deadline_timestamp_ms > env::block_timestamp_ms(),
-            "Deadline must be in the future"
-        );
-        Self {
-            proposal,
-            deadline_timestamp_ms,
-            votes: HashMap::new(),
-            total_voted_stake: 0,
-            result: None,
-            last_epoch_height: 0,
-        }
+    pub fn create_proposal(
+        &mut self,
+        proposal: String,
+        deadline_timestamp_ms: Option<Timestamp>,
+        options: Option<ProposalOptions>,
+    ) -> ProposalId {
+        self.internal_create_proposal(proposal, deadline_timestamp_ms, options.unwrap_or_default())
     }
 
-    /// Ping to update the votes according to current stake of validators.
-    pub fn ping(&mut self) {
-        require!(
-            env::block_timestamp_ms() < self.deadline_timestamp_ms,
-            "Voting deadline has already passed"
-        );
-        require!(self.result.is_none(), "Voting has already ended");
-        let cur_epoch_height = env::epoch_height();
-        if cur_epoch_height != self.last_epoch_height {
-            self.total_voted_stake = 0;
-            for (account_id, stake) in self.votes.iter_mut() {
-                let account_current_stake = validator_stake(account_id);
-                self.total_voted_stake += account_current_stake;
-                *stake = account_current_stake;
-            }
-            self.check_result();
-            self.last_epoch_height = cur_epoch_height;
-        }
+    /// Ping to update the votes of the proposal according to current stake of validators.
+    /// After an epoch change at most `limit` votes are refreshed per call, so it may need to be
+    /// called several times with a large number of votes. Returns whether the votes are up to
+    /// date with the current epoch.
+    pub fn ping(&mut self, proposal_id: ProposalId, limit: Option<u32>) -> bool {
+        self.internal_update_proposal(proposal_id, |proposal, config| {
+            proposal.ping(proposal_id, config, limit.unwrap_or(DEFAULT_PING_LIMIT))
+        })
+    }
+
+    /// Record the end of a proposal whose deadline passed without an approval or rejection.
+    /// The proposal becomes expired, or rejected if the `reject_on_expiry` option was set, and
+    /// its tally no longer changes. Can be called by anyone.
+    pub fn finalize(&mut self, proposal_id: ProposalId) {
+        self.internal_update_proposal(proposal_id, |proposal, config| {
+            proposal.finalize(proposal_id, config)
+        });
     }
 
-    /// Method for validators to vote with `Yes` or `No`.
-    /// The method is called by validator owners.
-    pub fn vote(&mut self, vote: Vote, staking_pool_id: AccountId) -> Promise {
+    /// Method for validators to vote with `Yes`, `No` or `Abstain` on the given proposal.
+    /// The method is called by validator owners, or by the voter they registered. A staking pool
+    /// can also call it for itself, which votes with its stake without checking the owner.
+    pub fn vote(
+        &mut self,
+        proposal_id: ProposalId,
+        vote: Vote,
+        staking_pool_id: AccountId,
+    ) -> PromiseOrValue<()> {
+        if env::predecessor_account_id() == staking_pool_id {
+            self.internal_vote(
+                proposal_id,
+                vote,
+                staking_pool_id.clone(),
+                staking_pool_id,
+                DEFAULT_PING_LIMIT,
+            );
+            return PromiseOrValue::Value(());
+        }
         ext_staking_pool::ext(staking_pool_id.clone())
             .with_static_gas(GET_OWNER_ID_GAS)
             .get_owner_id()
             .then(Self::ext(env::current_account_id()).on_get_pool_owner_id(
                 env::predecessor_account_id(),
+                proposal_id,
                 staking_pool_id,
                 vote,
             ))
+            .into()
     }
 
     /// Check the pool owner id and vote.
     #[private]
     pub fn on_get_pool_owner_id(
         &mut self,
-        pool_owner_id: AccountId,
+        voter_account_id: AccountId,
+        proposal_id: ProposalId,
         staking_pool_id: AccountId,
         vote: Vote,
         #[callback_result] pool_owner_id_result: Result<AccountId, PromiseError>,
     ) {
         if let Ok(actual_owner_id) = pool_owner_id_result {
             require!(
-                pool_owner_id == actual_owner_id,
-                "Voting is only allowed for the staking pool owner"
+                self.is_allowed_voter(&staking_pool_id, &actual_owner_id, &voter_account_id),
+                "Voting is only allowed for the staking pool owner or its voter"
+            );
+            self.internal_vote(
+                proposal_id,
+                vote,
+                staking_pool_id,
+                voter_account_id,
+                DEFAULT_PING_LIMIT,
             );
-            self.internal_vote(vote, staking_pool_id);
         } else {
             env::panic_str("Failed to get the staking pool owner id");
         }
     }
+}
 
-    /// Internal method for voting.
-    fn internal_vote(&mut self, vote: Vote, account_id: AccountId) {
-        self.ping();
-
-        let stake = validator_stake(&account_id);
-        require!(stake > 0, format!("{} is not a validator", account_id));
-
-        let account_stake = match vote {
-            Vote::Yes => stake,
-            Vote::No => 0,
-        };
-
-        let voted_stake = self.votes.remove(&account_id).unwrap_or_default();
-        require!(
-            voted_stake <= self.total_voted_stake,
-            format!(
-                "invariant: voted stake {} is more than total voted stake {}",
-                voted_stake, self.total_voted_stake
+impl Contract {
+    fn internal_vote(
+        &mut self,
+        proposal_id: ProposalId,
+        vote: Vote,
+        staking_pool_id: AccountId,
+        voter_account_id: AccountId,
+        ping_limit: u32,
+    ) {
+        self.internal_update_proposal(proposal_id, |proposal, config| {
+            proposal.vote(
+                proposal_id,
+                config,
+                vote,
+                staking_pool_id,
+                voter_account_id,
+                ping_limit,
             )
-        );
-        self.total_voted_stake = self.total_voted_stake + account_stake - voted_stake;
-        if account_stake > 0 {
-            self.votes.insert(account_id.clone(), account_stake);
-            self.check_result();
+        });
+    }
+
+    /// Update the proposal with `f`, and notify the subscribers if it was approved or rejected
+    /// by the update.
+    fn internal_update_proposal<R>(
+        &mut self,
+        proposal_id: ProposalId,
+        f: impl FnOnce(&mut Proposal, &Config) -> R,
+    ) -> R {
+        let config = self.config.clone();
+        let proposal = self.internal_get_proposal_mut(proposal_id);
+        let had_result = proposal.result.is_some();
+        let value = f(proposal, &config);
+        let finalized = !had_result
+            && matches!(
+                proposal.result.as_ref().map(|result| result.outcome),
+                Some(Outcome::Approved | Outcome::Rejected)
+            );
+        if finalized {
+            self.internal_notify_subscribers(proposal_id);
         }
-        // emit event
-        Event::Voted {
-            validator_id: &account_id,
-            vote: &vote,
+        value
+    }
+
+    fn internal_create_proposal(
+        &mut self,
+        description: String,
+        deadline_timestamp_ms: Option<Timestamp>,
+        options: ProposalOptions,
+    ) -> ProposalId {
+        let proposal_id = self.num_proposals;
+        let proposal = Proposal::new(proposal_id, description, deadline_timestamp_ms, options);
+        Event::ProposalCreated {
+            proposal_id: &proposal_id,
+            proposal: &proposal.description,
+            deadline_timestamp_ms: &proposal.deadline_timestamp_ms.map(U64::from),
+            deadline_epoch_height: &proposal.deadline_epoch_height.map(U64::from),
+            start_timestamp_ms: &proposal.start_timestamp_ms.map(U64::from),
+            start_epoch_height: &proposal.start_epoch_height.map(U64::from),
         }
         .emit();
+        self.proposals
+            .insert(proposal_id, VersionedProposal(proposal));
+        self.num_proposals += 1;
+        proposal_id
     }
 
-    /// Check whether the voting has ended.
-    fn check_result(&mut self) {
-        require!(
-            self.result.is_none(),
-            "check result is called after result is already set"
-        );
-        let total_stake = validator_total_stake();
-        if self.total_voted_stake > total_stake * 2 / 3 {
-            self.result = Some(env::block_timestamp_ms());
-            Event::ProposalApproved {
-                proposal: &self.proposal,
-                approval_timestamp_ms: &U64::from(env::block_timestamp_ms()),
-                deadline_timestamp_ms: &U64::from(self.deadline_timestamp_ms),
-                voted_stake: &U128::from(self.total_voted_stake),
-                total_stake: &U128::from(total_stake),
-                num_votes: &U64::from(self.votes.len() as u64),
-            }
-            .emit();
-        }
+    fn internal_get_proposal(&self, proposal_id: ProposalId) -> &Proposal {
+        self.proposals
+            .get(&proposal_id)
+            .map(|proposal| &proposal.0)
+            .unwrap_or_else(|| env::panic_str("Proposal not found"))
+    }
+
+    fn internal_get_proposal_mut(&mut self, proposal_id: ProposalId) -> &mut Proposal {
+        self.proposals
+            .get_mut(&proposal_id)
+            .map(|proposal| &mut proposal.0)
+            .unwrap_or_else(|| env::panic_str("Proposal not found"))
     }
 }
 
 /// View methods
 #[near]
 impl Contract {
-    /// Returns a pair of `total_voted_stake` and the total stake.
+    /// Returns the voting rules applied to all proposals.
+    pub fn get_config(&self) -> Config {
+        self.config.clone()
+    }
+
+    /// Returns the number of proposals in the registry. Proposal ids are in `0..num_proposals`.
+    pub fn get_num_proposals(&self) -> u64 {
+        self.num_proposals
+    }
+
+    /// Returns a pair of the stake voted `Yes` and the total stake. Once the voting has ended,
+    /// the total stake is the one the outcome was decided with.
     /// Note: as a view method, it doesn't recompute the active stake. May need to call `ping` to
     /// update the active stake.
-    pub fn get_total_voted_stake(&self) -> (U128, U128) {
-        (
-            self.total_voted_stake.into(),
-            validator_total_stake().into(),
-        )
+    pub fn get_total_voted_stake(&self, proposal_id: ProposalId) -> (U128, U128) {
+        let proposal = self.internal_get_proposal(proposal_id);
+        let total_stake = match &proposal.final_tally {
+            Some(final_tally) => final_tally.total_stake,
+            None => validator_total_stake().into(),
+        };
+        (proposal.tally.yes, total_stake)
+    }
+
+    /// Returns the stake voted for each choice.
+    /// Note: as a view method, it doesn't recompute the active stake. May need to call `ping` to
+    /// update the active stake.
+    pub fn get_tally(&self, proposal_id: ProposalId) -> Tally {
+        self.internal_get_proposal(proposal_id).tally.clone()
+    }
+
+    /// Returns all votes of the proposal with the choice and stake of each validator.
+    /// Note: as a view method, it doesn't recompute the active stake. May need to call `ping` to
+    /// update the active stake.
+    pub fn get_votes(&self, proposal_id: ProposalId) -> HashMap<AccountId, VoteRecord> {
+        self.internal_get_proposal(proposal_id)
+            .votes
+            .iter()
+            .map(|(account_id, record)| (account_id.clone(), record.clone()))
+            .collect()
+    }
+
+    /// Returns the number of validators that voted on the proposal.
+    pub fn get_num_votes(&self, proposal_id: ProposalId) -> u64 {
+        self.internal_get_proposal(proposal_id).votes.len() as u64
     }
 
-    /// Returns all active votes.
+    /// Returns up to `limit` votes of the proposal starting at `from_index`. Votes are ordered by
+    /// the time each validator first voted, so the order is stable while the voting goes on.
     /// Note: as a view method, it doesn't recompute the active stake. May need to call `ping` to
     /// update the active stake.
-    pub fn get_votes(&self) -> HashMap<AccountId, U128> {
-        self.votes
+    pub fn get_votes_paged(
+        &self,
+        proposal_id: ProposalId,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<(AccountId, VoteRecord)> {
+        self.internal_get_proposal(proposal_id)
+            .votes
             .iter()
-            .map(|(account_id, stake)| (account_id.clone(), (*stake).into()))
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .map(|(account_id, record)| (account_id.clone(), record.clone()))
             .collect()
     }
 
+    /// Returns the vote of the given validator on the proposal, if any.
+    pub fn get_vote(&self, proposal_id: ProposalId, account_id: AccountId) -> Option<VoteRecord> {
+        self.internal_get_proposal(proposal_id)
+            .votes
+            .get(&account_id)
+            .cloned()
+    }
+
+    /// Returns the current state of the proposal.
+    pub fn get_status(&self, proposal_id: ProposalId) -> ProposalStatus {
+        self.internal_get_proposal(proposal_id).status()
+    }
+
     /// Get the timestamp of when the voting finishes. `None` means the voting hasn't ended yet.
-    pub fn get_result(&self) -> Option<Timestamp> {
-        self.result
+    pub fn get_result(&self, proposal_id: ProposalId) -> Option<Timestamp> {
+        self.internal_get_proposal(proposal_id)
+            .result
+            .as_ref()
+            .map(|result| result.timestamp_ms)
+    }
+
+    /// Get the outcome of the proposal and the timestamp of when the voting finishes.
+    /// `None` means the voting hasn't ended yet.
+    pub fn get_proposal_result(&self, proposal_id: ProposalId) -> Option<ProposalResult> {
+        self.internal_get_proposal(proposal_id).result.clone()
+    }
+
+    /// Returns the deadline timestamp in milliseconds. `None` if the deadline is an epoch height.
+    pub fn get_deadline_timestamp(&self, proposal_id: ProposalId) -> Option<Timestamp> {
+        self.internal_get_proposal(proposal_id)
+            .deadline_timestamp_ms
+    }
+
+    /// Returns the deadline epoch height. `None` if the deadline is a timestamp.
+    pub fn get_deadline_epoch_height(&self, proposal_id: ProposalId) -> Option<EpochHeight> {
+        self.internal_get_proposal(proposal_id)
+            .deadline_epoch_height
+    }
+
+    /// Returns the current epoch height, to compare with deadlines and starts given in epochs.
+    pub fn get_epoch_height(&self) -> EpochHeight {
+        env::epoch_height()
+    }
+
+    /// Returns the timestamp voting opens at, if it was scheduled.
+    pub fn get_start_timestamp(&self, proposal_id: ProposalId) -> Option<Timestamp> {
+        self.internal_get_proposal(proposal_id).start_timestamp_ms
     }
 
-    /// Returns the deadline timestamp in milliseconds.
-    pub fn get_deadline_timestamp(&self) -> Timestamp {
-        self.deadline_timestamp_ms
+    /// Returns the epoch height voting opens at, if it was scheduled.
+    pub fn get_start_epoch_height(&self, proposal_id: ProposalId) -> Option<EpochHeight> {
+        self.internal_get_proposal(proposal_id).start_epoch_height
     }
 
     /// Returns the proposal.
-    pub fn get_proposal(&self) -> String {
-        self.proposal.clone()
+    pub fn get_proposal(&self, proposal_id: ProposalId) -> String {
+        self.internal_get_proposal(proposal_id).description.clone()
+    }
+
+    /// Returns the structured description of the proposal, if it was given at creation.
+    pub fn get_proposal_metadata(&self, proposal_id: ProposalId) -> Option<ProposalMetadata> {
+        self.internal_get_proposal(proposal_id).metadata.clone()
     }
 }
 
@@ -229,8 +416,15 @@ impl Contract {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use near_sdk::test_utils::{accounts, VMContextBuilder};
-    use near_sdk::{env, test_vm_config, testing_env, Gas, NearToken, RuntimeFeesConfig};
+    use near_sdk::borsh;
+    use near_sdk::json_types::Base64VecU8;
+    use near_sdk::mock::MockAction;
+    use near_sdk::serde_json;
+    use near_sdk::test_utils::{accounts, get_created_receipts, get_logs, VMContextBuilder};
+    use near_sdk::{
+        env, test_vm_config, testing_env, Gas, NearToken, PromiseResult, PublicKey,
+        RuntimeFeesConfig,
+    };
 
     fn validators() -> HashMap<String, NearToken> {
         (0..300)
@@ -247,12 +441,25 @@ mod tests {
     }
 
     fn get_contract() -> Contract {
+        get_contract_with_config(None, None)
+    }
+
+    fn get_contract_with_config(threshold: Option<Ratio>, quorum: Option<Ratio>) -> Contract {
         Contract::new(
             "Test proposal".to_string(),
-            env::block_timestamp_ms() + 1000,
+            Some(env::block_timestamp_ms() + 1000),
+            threshold,
+            quorum,
+            Some(owner()),
+            None,
+            None,
         )
     }
 
+    fn owner() -> AccountId {
+        "owner".to_string().parse().unwrap()
+    }
+
     fn voting_contract_id() -> AccountId {
         "voting-contract".to_string().parse().unwrap()
     }
@@ -270,7 +477,7 @@ mod tests {
             .signer_account_id(accounts(1))
             .predecessor_account_id(predecessor_account_id.clone())
             .storage_usage(1000)
-            .prepaid_gas(Gas::from_tgas(200))
+            .prepaid_gas(Gas::from_tgas(300))
             .is_view(false)
             .epoch_height(epoch_height)
             .clone()
@@ -299,12 +506,14 @@ mod tests {
 
     fn vote_with_account(
         contract: &mut Contract,
+        proposal_id: ProposalId,
         vote: Vote,
         staking_pool_id: &AccountId,
         account: &AccountId,
     ) {
         contract.on_get_pool_owner_id(
             account.clone(),
+            proposal_id,
             staking_pool_id.clone(),
             vote,
             Ok(pool_owner()),
@@ -312,7 +521,16 @@ mod tests {
     }
 
     fn vote(contract: &mut Contract, vote: Vote, staking_pool_id: &AccountId) {
-        vote_with_account(contract, vote, staking_pool_id, &pool_owner());
+        vote_with_account(contract, 0, vote, staking_pool_id, &pool_owner());
+    }
+
+    fn vote_on_proposal(
+        contract: &mut Contract,
+        proposal_id: ProposalId,
+        vote: Vote,
+        staking_pool_id: &AccountId,
+    ) {
+        vote_with_account(contract, proposal_id, vote, staking_pool_id, &pool_owner());
     }
 
     #[test]
@@ -354,13 +572,13 @@ mod tests {
         let mut contract = get_contract();
         // vote
         vote(&mut contract, Vote::Yes, &validator_id);
-        assert!(contract.get_result().is_some());
+        assert!(contract.get_result(0).is_some());
         // vote again. should panic because voting has ended
         vote(&mut contract, Vote::Yes, &validator_id);
     }
 
     #[test]
-    #[should_panic(expected = "Voting is only allowed for the staking pool owner")]
+    #[should_panic(expected = "Voting is only allowed for the staking pool owner or its voter")]
     fn test_only_pool_owner_can_vote() {
         let validator_id = validator(0);
         let context = get_context(&voting_contract_id());
@@ -372,7 +590,7 @@ mod tests {
         let mut contract = get_contract();
         // vote with an account that is not the pool owner.
         // should panic because only the pool owner can vote.
-        vote_with_account(&mut contract, Vote::Yes, &validator_id, &accounts(0));
+        vote_with_account(&mut contract, 0, Vote::Yes, &validator_id, &accounts(0));
     }
 
     #[test]
@@ -390,20 +608,35 @@ mod tests {
             context.is_view(true);
             set_context(&context);
             assert_eq!(
-                contract.get_total_voted_stake(),
+                contract.get_total_voted_stake(0),
                 (U128::from(10 * (i + 1) as u128), U128::from(3000))
             );
             // check votes
-            let expected_votes: HashMap<AccountId, U128> =
-                (0..=i).map(|j| (validator(j), U128::from(10))).collect();
-            assert_eq!(contract.get_votes(), expected_votes);
-            assert_eq!(contract.get_votes().len() as u64, i + 1);
+            let expected_votes: HashMap<AccountId, VoteRecord> = (0..=i)
+                .map(|j| {
+                    (
+                        validator(j),
+                        VoteRecord {
+                            choice: Vote::Yes,
+                            stake: U128::from(10),
+                            voter_account_id: Some(pool_owner()),
+                            voted_at_ms: env::block_timestamp_ms(),
+                            voted_epoch: 0,
+                            last_updated_epoch: 0,
+                        },
+                    )
+                })
+                .collect();
+            assert_eq!(contract.get_votes(0), expected_votes);
+            assert_eq!(contract.get_votes(0).len() as u64, i + 1);
             // check voting result
             if i < 200 {
-                assert!(contract.get_result().is_none());
+                assert!(contract.get_result(0).is_none());
             } else {
-                assert!(contract.get_result().is_some());
+                assert!(contract.get_result(0).is_some());
             }
+            context.is_view(false);
+            set_context(&context);
         }
     }
 
@@ -417,14 +650,19 @@ mod tests {
             // vote by each validator
             let context = get_context_with_epoch_height(&voting_contract_id(), i);
             set_context(&context);
+            // refresh the votes for the new epoch in batches before voting
+            while !contract.ping(0, None) {
+                set_context(&context);
+            }
+            set_context(&context);
             vote(&mut contract, Vote::Yes, &validator(i));
             // check votes
-            assert_eq!(contract.get_votes().len() as u64, i + 1);
+            assert_eq!(contract.get_votes(0).len() as u64, i + 1);
             // check voting result
             if i < 200 {
-                assert!(contract.get_result().is_none());
+                assert!(contract.get_result(0).is_none());
             } else {
-                assert!(contract.get_result().is_some());
+                assert!(contract.get_result(0).is_some());
             }
         }
     }
@@ -445,8 +683,8 @@ mod tests {
         validators.insert(validator(1).to_string(), NearToken::from_yoctonear(50));
         let context = get_context_with_epoch_height(&voting_contract_id(), 2);
         set_context_and_validators(&context, &validators);
-        contract.ping();
-        assert!(contract.get_result().is_some());
+        contract.ping(0, None);
+        assert!(contract.get_result(0).is_some());
     }
 
     #[test]
@@ -454,81 +692,2472 @@ mod tests {
         let validators: HashMap<String, NearToken> = HashMap::from_iter(vec![
             (validator(1).to_string(), NearToken::from_yoctonear(10)),
             (validator(2).to_string(), NearToken::from_yoctonear(10)),
+            (validator(3).to_string(), NearToken::from_yoctonear(20)),
         ]);
         let context = get_context_with_epoch_height(&voting_contract_id(), 1);
         set_context_and_validators(&context, &validators);
         let mut contract = get_contract();
         // vote YES at epoch 1
         vote(&mut contract, Vote::Yes, &validator(1));
-        assert_eq!(contract.get_votes().len(), 1);
+        assert_eq!(contract.get_votes(0).len(), 1);
         // vote NO at epoch 2
         let context = get_context_with_epoch_height(&voting_contract_id(), 2);
         set_context_and_validators(&context, &validators);
         vote(&mut contract, Vote::No, &validator(1));
-        assert!(contract.get_votes().is_empty());
+        assert_eq!(contract.get_votes(0).len(), 1);
+        assert_eq!(contract.get_votes(0)[&validator(1)].choice, Vote::No);
+        assert_eq!(contract.get_tally(0).yes, U128::from(0));
+        assert_eq!(contract.get_tally(0).no, U128::from(10));
         // vote YES at epoch 3
         let context = get_context_with_epoch_height(&voting_contract_id(), 3);
         set_context_and_validators(&context, &validators);
         vote(&mut contract, Vote::Yes, &validator(1));
-        assert_eq!(contract.get_votes().len(), 1);
+        assert_eq!(contract.get_votes(0).len(), 1);
+        assert_eq!(contract.get_votes(0)[&validator(1)].choice, Vote::Yes);
+        assert_eq!(contract.get_tally(0).yes, U128::from(10));
+        assert_eq!(contract.get_tally(0).no, U128::from(0));
     }
 
     #[test]
-    fn test_validator_kick_out() {
+    fn test_tally_by_choice() {
         let mut validators: HashMap<String, NearToken> = HashMap::from_iter(vec![
-            (validator(1).to_string(), NearToken::from_yoctonear(40)),
-            (validator(2).to_string(), NearToken::from_yoctonear(10)),
-            (validator(3).to_string(), NearToken::from_yoctonear(10)),
+            (validator(1).to_string(), NearToken::from_yoctonear(10)),
+            (validator(2).to_string(), NearToken::from_yoctonear(20)),
+            (validator(3).to_string(), NearToken::from_yoctonear(30)),
+            (validator(4).to_string(), NearToken::from_yoctonear(100)),
         ]);
         let context = get_context_with_epoch_height(&voting_contract_id(), 1);
         set_context_and_validators(&context, &validators);
         let mut contract = get_contract();
-        // vote at epoch 1
         vote(&mut contract, Vote::Yes, &validator(1));
-        assert_eq!((contract.get_total_voted_stake().0).0, 40);
-        assert_eq!(contract.get_votes().len(), 1);
-        // remove validator at epoch 2
-        validators.remove(&validator(1).to_string());
+        vote(&mut contract, Vote::No, &validator(2));
+        vote(&mut contract, Vote::Abstain, &validator(3));
+        assert_eq!(
+            contract.get_tally(0),
+            Tally {
+                yes: U128::from(10),
+                no: U128::from(20),
+                abstain: U128::from(30),
+            }
+        );
+        assert_eq!(contract.get_votes(0).len(), 3);
+        assert_eq!(contract.get_votes(0)[&validator(3)].choice, Vote::Abstain);
+
+        // stake changes at epoch 2 are applied to each choice by ping
+        validators.insert(validator(2).to_string(), NearToken::from_yoctonear(25));
+        validators.remove(&validator(3).to_string());
         let context = get_context_with_epoch_height(&voting_contract_id(), 2);
         set_context_and_validators(&context, &validators);
-        // ping will update total voted stake
-        contract.ping();
-        assert_eq!((contract.get_total_voted_stake().0).0, 0);
-        assert_eq!(contract.get_votes().len(), 1);
-        // validator(1) is back to validator set at epoch 3
-        validators.insert(validator(1).to_string(), NearToken::from_yoctonear(40));
+        contract.ping(0, None);
+        assert_eq!(
+            contract.get_tally(0),
+            Tally {
+                yes: U128::from(10),
+                no: U128::from(25),
+                abstain: U128::from(0),
+            }
+        );
+        assert_eq!(contract.get_votes(0)[&validator(3)].stake, U128::from(0));
+    }
+
+    #[test]
+    fn test_ping_in_batches() {
+        let mut validators = validators();
+        let context = get_context_with_epoch_height(&voting_contract_id(), 1);
+        set_context_and_validators(&context, &validators);
+        let mut contract = get_contract();
+        for i in 0..300 {
+            set_context_and_validators(&context, &validators);
+            vote(&mut contract, Vote::Abstain, &validator(i));
+        }
+        let tally_at_epoch_1 = Tally {
+            abstain: U128::from(3000),
+            ..Default::default()
+        };
+        assert_eq!(contract.get_tally(0), tally_at_epoch_1);
+
+        // stake changes at epoch 2 are only applied once all votes are refreshed
+        validators.insert(validator(0).to_string(), NearToken::from_yoctonear(1000));
+        validators.remove(&validator(299).to_string());
+        let context = get_context_with_epoch_height(&voting_contract_id(), 2);
+        set_context_and_validators(&context, &validators);
+        assert!(!contract.ping(0, Some(10)));
+        assert_eq!(contract.get_tally(0), tally_at_epoch_1);
+
+        // votes are still accepted while the refresh is pending. validator(0) is refreshed
+        // already so its vote counts towards the new tally.
+        set_context_and_validators(&context, &validators);
+        vote(&mut contract, Vote::Yes, &validator(0));
+        assert_eq!(contract.get_tally(0), tally_at_epoch_1);
+        assert_eq!(contract.get_status(0), ProposalStatus::Active);
+
+        set_context_and_validators(&context, &validators);
+        assert!(!contract.ping(0, None));
+        set_context_and_validators(&context, &validators);
+        assert!(contract.ping(0, None));
+        assert_eq!(
+            contract.get_tally(0),
+            Tally {
+                yes: U128::from(1000),
+                abstain: U128::from(2980),
+                ..Default::default()
+            }
+        );
+        let votes = contract.get_votes(0);
+        assert_eq!(votes[&validator(0)].stake, U128::from(1000));
+        assert_eq!(votes[&validator(0)].last_updated_epoch, 2);
+        assert_eq!(votes[&validator(299)].stake, U128::from(0));
+        assert!(contract.ping(0, Some(10)));
+
+        // validator(298) is not refreshed yet, so its vote is counted when the refresh gets to it
         let context = get_context_with_epoch_height(&voting_contract_id(), 3);
         set_context_and_validators(&context, &validators);
-        // ping will update total voted stake after validator(1) is back
-        contract.ping();
-        assert_eq!((contract.get_total_voted_stake().0).0, 40);
-        assert_eq!(contract.get_votes().len(), 1);
+        assert!(!contract.ping(0, Some(10)));
+        set_context_and_validators(&context, &validators);
+        vote(&mut contract, Vote::No, &validator(298));
+        assert_eq!(contract.get_votes(0)[&validator(298)].last_updated_epoch, 2);
+
+        // an epoch change in the middle of a refresh starts it over
+        validators.insert(validator(1).to_string(), NearToken::from_yoctonear(500));
+        let context = get_context_with_epoch_height(&voting_contract_id(), 4);
+        for _ in 0..2 {
+            set_context_and_validators(&context, &validators);
+            assert!(!contract.ping(0, None));
+        }
+        set_context_and_validators(&context, &validators);
+        assert!(contract.ping(0, None));
+        assert_eq!(
+            contract.get_tally(0),
+            Tally {
+                yes: U128::from(1000),
+                no: U128::from(10),
+                abstain: U128::from(3460),
+            }
+        );
     }
 
     #[test]
-    fn test_init_contract() {
-        let contract = get_contract();
-        assert_eq!(contract.get_proposal(), "Test proposal");
+    fn test_votes_paged() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+        for i in [5, 3, 8, 1] {
+            vote(&mut contract, Vote::Yes, &validator(i));
+        }
+        // changing the vote keeps the position of the validator
+        vote(&mut contract, Vote::No, &validator(3));
+
+        assert_eq!(contract.get_num_votes(0), 4);
+        let ids = |votes: Vec<(AccountId, VoteRecord)>| {
+            votes
+                .into_iter()
+                .map(|(account_id, _)| account_id)
+                .collect::<Vec<_>>()
+        };
         assert_eq!(
-            contract.get_deadline_timestamp(),
-            env::block_timestamp_ms() + 1000
+            ids(contract.get_votes_paged(0, 0, 3)),
+            vec![validator(5), validator(3), validator(8)]
         );
+        assert_eq!(ids(contract.get_votes_paged(0, 3, 3)), vec![validator(1)]);
+        assert!(contract.get_votes_paged(0, 4, 3).is_empty());
+
+        assert_eq!(contract.get_vote(0, validator(3)).unwrap().choice, Vote::No);
+        assert!(contract.get_vote(0, validator(2)).is_none());
     }
 
     #[test]
-    #[should_panic(expected = "Proposal cannot be empty")]
-    fn test_init_with_empty_proposal() {
-        let context = VMContextBuilder::new();
+    fn test_vote_record() {
+        let context = get_context_with_epoch_height(&voting_contract_id(), 1);
+        set_context(&context);
+        let mut contract = get_contract();
+        let voted_at_ms = env::block_timestamp_ms();
+        vote(&mut contract, Vote::Yes, &validator(1));
+        assert_eq!(
+            contract.get_vote(0, validator(1)).unwrap(),
+            VoteRecord {
+                choice: Vote::Yes,
+                stake: U128::from(10),
+                voter_account_id: Some(pool_owner()),
+                voted_at_ms,
+                voted_epoch: 1,
+                last_updated_epoch: 1,
+            }
+        );
+
+        // a new vote at a later epoch replaces the time and epoch of the record
+        let mut context = get_context_with_epoch_height(&voting_contract_id(), 2);
+        context.block_timestamp((voted_at_ms + 500) * 1_000_000);
         set_context(&context);
-        Contract::new("".to_string(), env::block_timestamp_ms() + 1000);
+        vote(&mut contract, Vote::No, &validator(1));
+        let record = contract.get_vote(0, validator(1)).unwrap();
+        assert_eq!(record.choice, Vote::No);
+        assert_eq!(record.voted_at_ms, voted_at_ms + 500);
+        assert_eq!(record.voted_epoch, 2);
+        assert_eq!(record.last_updated_epoch, 2);
+    }
+
+    fn set_voter(
+        contract: &mut Contract,
+        staking_pool_id: &AccountId,
+        voter_id: Option<AccountId>,
+    ) {
+        contract.on_get_pool_owner_id_for_voter(
+            pool_owner(),
+            staking_pool_id.clone(),
+            voter_id,
+            Ok(pool_owner()),
+        );
     }
 
     #[test]
-    #[should_panic(expected = "Deadline must be in the future")]
-    fn test_init_with_past_deadline() {
-        let context = VMContextBuilder::new();
+    fn test_voter_delegation() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+        set_voter(&mut contract, &validator(1), Some(accounts(2)));
+        assert_eq!(
+            contract.get_voter(validator(1)),
+            Some(Delegation {
+                voter_id: accounts(2),
+                owner_id: pool_owner(),
+            })
+        );
+
+        vote_with_account(&mut contract, 0, Vote::Yes, &validator(1), &accounts(2));
+        let record = contract.get_vote(0, validator(1)).unwrap();
+        assert_eq!(record.voter_account_id, Some(accounts(2)));
+        // the owner can still vote
+        vote(&mut contract, Vote::No, &validator(1));
+        assert_eq!(contract.get_vote(0, validator(1)).unwrap().choice, Vote::No);
+
+        set_voter(&mut contract, &validator(1), None);
+        assert!(contract.get_voter(validator(1)).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Voting is only allowed for the staking pool owner or its voter")]
+    fn test_vote_by_removed_voter() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+        set_voter(&mut contract, &validator(1), Some(accounts(2)));
+        set_voter(&mut contract, &validator(1), None);
+        vote_with_account(&mut contract, 0, Vote::Yes, &validator(1), &accounts(2));
+    }
+
+    #[test]
+    #[should_panic(expected = "Voting is only allowed for the staking pool owner or its voter")]
+    fn test_vote_by_voter_after_owner_change() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+        set_voter(&mut contract, &validator(1), Some(accounts(2)));
+        // the staking pool is now owned by another account
+        contract.on_get_pool_owner_id(accounts(2), 0, validator(1), Vote::Yes, Ok(accounts(3)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the staking pool owner can change its voter")]
+    fn test_set_voter_by_non_owner() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+        contract.on_get_pool_owner_id_for_voter(
+            accounts(2),
+            validator(1),
+            Some(accounts(2)),
+            Ok(pool_owner()),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "user-pool is not a validator")]
+    fn test_set_voter_for_non_validator() {
+        let context = get_context(&pool_owner());
+        set_context(&context);
+        let mut contract = get_contract();
+        let _ = contract.set_voter("user-pool".parse().unwrap(), accounts(2));
+    }
+
+    #[test]
+    fn test_vote_by_staking_pool() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+
+        let context = get_context(&validator(1));
+        set_context(&context);
+        assert!(matches!(
+            contract.vote(0, Vote::Yes, validator(1)),
+            PromiseOrValue::Value(())
+        ));
+        let record = contract.get_vote(0, validator(1)).unwrap();
+        assert_eq!(record.choice, Vote::Yes);
+        assert_eq!(record.stake, U128::from(10));
+        assert_eq!(record.voter_account_id, Some(validator(1)));
+    }
+
+    #[test]
+    #[should_panic(expected = "user-pool is not a validator")]
+    fn test_vote_by_non_validator_staking_pool() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+
+        let staking_pool_id: AccountId = "user-pool".parse().unwrap();
+        let context = get_context(&staking_pool_id);
+        set_context(&context);
+        let _ = contract.vote(0, Vote::Yes, staking_pool_id);
+    }
+
+    fn owner_id_result(owner_id: &AccountId) -> PromiseResult {
+        PromiseResult::Successful(near_sdk::serde_json::to_vec(owner_id).unwrap())
+    }
+
+    #[test]
+    fn test_vote_batch() {
+        let context = get_context(&voting_contract_id());
         set_context(&context);
-        Contract::new("Test proposal".to_string(), env::block_timestamp_ms());
+        let mut contract = get_contract();
+
+        let staking_pool_ids = vec![
+            validator(1),
+            validator(2),
+            validator(3),
+            "user-pool".parse().unwrap(),
+        ];
+        testing_env!(
+            context.build(),
+            test_vm_config(),
+            RuntimeFeesConfig::test(),
+            validators(),
+            vec![
+                owner_id_result(&pool_owner()),
+                owner_id_result(&accounts(2)),
+                PromiseResult::Failed,
+                owner_id_result(&pool_owner()),
+            ]
+        );
+        let results = contract.on_get_pool_owner_ids(pool_owner(), 0, staking_pool_ids, Vote::Yes);
+        assert_eq!(
+            results
+                .iter()
+                .map(|result| result.error.as_deref())
+                .collect::<Vec<_>>(),
+            vec![
+                None,
+                Some("Voting is only allowed for the staking pool owner or its voter"),
+                Some("Failed to get the staking pool owner id"),
+                Some("user-pool is not a validator"),
+            ]
+        );
+        assert_eq!(contract.get_num_votes(0), 1);
+        assert_eq!(
+            contract.get_vote(0, validator(1)).unwrap().choice,
+            Vote::Yes
+        );
+    }
+
+    #[test]
+    fn test_vote_batch_after_approval() {
+        let validators: HashMap<String, NearToken> = HashMap::from_iter(vec![
+            (validator(1).to_string(), NearToken::from_yoctonear(80)),
+            (validator(2).to_string(), NearToken::from_yoctonear(20)),
+        ]);
+        let context = get_context(&voting_contract_id());
+        set_context_and_validators(&context, &validators);
+        let mut contract = get_contract();
+
+        testing_env!(
+            context.build(),
+            test_vm_config(),
+            RuntimeFeesConfig::test(),
+            validators,
+            vec![
+                owner_id_result(&pool_owner()),
+                owner_id_result(&pool_owner())
+            ]
+        );
+        let results = contract.on_get_pool_owner_ids(
+            pool_owner(),
+            0,
+            vec![validator(1), validator(2)],
+            Vote::Yes,
+        );
+        assert_eq!(results[0].error, None);
+        assert_eq!(
+            results[1].error.as_deref(),
+            Some("Voting has already ended")
+        );
+        assert_eq!(contract.get_status(0), ProposalStatus::Approved);
+    }
+
+    #[test]
+    fn test_vote_batch_after_deadline() {
+        let mut context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+
+        context.block_timestamp((env::block_timestamp_ms() + 2000) * 1_000_000);
+        testing_env!(
+            context.build(),
+            test_vm_config(),
+            RuntimeFeesConfig::test(),
+            validators(),
+            vec![owner_id_result(&pool_owner())]
+        );
+        let results =
+            contract.on_get_pool_owner_ids(pool_owner(), 0, vec![validator(1)], Vote::Yes);
+        assert_eq!(
+            results[0].error.as_deref(),
+            Some("Voting deadline has already passed")
+        );
+        assert_eq!(contract.get_num_votes(0), 0);
+    }
+
+    #[test]
+    fn test_vote_batch_pings_once() {
+        let context = get_context_with_epoch_height(&voting_contract_id(), 1);
+        set_context(&context);
+        let mut contract = get_contract();
+        for i in 0..150 {
+            set_context(&context);
+            vote(&mut contract, Vote::Abstain, &validator(i));
+        }
+
+        // only the first 100 votes are refreshed for the new epoch, by a single ping
+        let context = get_context_with_epoch_height(&voting_contract_id(), 2);
+        testing_env!(
+            context.build(),
+            test_vm_config(),
+            RuntimeFeesConfig::test(),
+            validators(),
+            vec![
+                owner_id_result(&pool_owner()),
+                owner_id_result(&pool_owner()),
+                owner_id_result(&pool_owner()),
+            ]
+        );
+        let results = contract.on_get_pool_owner_ids(
+            pool_owner(),
+            0,
+            vec![validator(200), validator(201), validator(202)],
+            Vote::Yes,
+        );
+        assert!(results.iter().all(|result| result.error.is_none()));
+        assert_eq!(contract.get_num_votes(0), 153);
+        set_context(&context);
+        assert!(!contract.ping(0, Some(10)));
+        assert!(contract.ping(0, Some(43)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot vote for more than 10 staking pools")]
+    fn test_vote_batch_too_many_pools() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+        let _ = contract.vote_batch(0, Vote::Yes, (0..11).map(validator).collect());
+    }
+
+    #[test]
+    #[should_panic(expected = "Duplicate staking pools")]
+    fn test_vote_batch_duplicate_pools() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+        let _ = contract.vote_batch(0, Vote::Yes, vec![validator(1), validator(1)]);
+    }
+
+    /// Creates a proposal with a commit phase of 500 ms and a deadline in 1000 ms.
+    fn create_commit_reveal_proposal(contract: &mut Contract) -> ProposalId {
+        contract.create_proposal(
+            "Secret proposal".to_string(),
+            Some(env::block_timestamp_ms() + 1000),
+            Some(ProposalOptions {
+                commit_deadline_timestamp_ms: Some(env::block_timestamp_ms() + 500),
+                ..Default::default()
+            }),
+        )
+    }
+
+    fn commit(
+        contract: &mut Contract,
+        proposal_id: ProposalId,
+        staking_pool_id: &AccountId,
+        vote: Vote,
+        salt: &str,
+    ) {
+        contract.on_get_pool_owner_id_for_commit(
+            pool_owner(),
+            proposal_id,
+            staking_pool_id.clone(),
+            vote_commitment(&vote, salt),
+            Ok(pool_owner()),
+        );
+    }
+
+    fn set_reveal_phase_context(context: &mut VMContextBuilder) {
+        let now_ms = env::block_timestamp_ms();
+        context.block_timestamp((now_ms + 600) * 1_000_000);
+        set_context(context);
+    }
+
+    #[test]
+    fn test_commit_reveal() {
+        let mut context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+        let proposal_id = create_commit_reveal_proposal(&mut contract);
+
+        commit(
+            &mut contract,
+            proposal_id,
+            &validator(1),
+            Vote::Yes,
+            "salt-1",
+        );
+        commit(
+            &mut contract,
+            proposal_id,
+            &validator(2),
+            Vote::No,
+            "salt-2",
+        );
+        // a new commitment replaces the previous one
+        commit(
+            &mut contract,
+            proposal_id,
+            &validator(2),
+            Vote::Abstain,
+            "salt-3",
+        );
+        assert_eq!(
+            contract
+                .get_commitment(proposal_id, validator(2))
+                .unwrap()
+                .hash,
+            vote_commitment(&Vote::Abstain, "salt-3")
+        );
+        // committed votes are not counted
+        assert_eq!(contract.get_num_votes(proposal_id), 0);
+        assert_eq!(contract.get_tally(proposal_id), Tally::default());
+
+        set_reveal_phase_context(&mut context);
+        contract.reveal_vote(proposal_id, validator(1), Vote::Yes, "salt-1".to_string());
+        contract.reveal_vote(
+            proposal_id,
+            validator(2),
+            Vote::Abstain,
+            "salt-3".to_string(),
+        );
+        assert_eq!(
+            contract.get_tally(proposal_id),
+            Tally {
+                yes: U128::from(10),
+                abstain: U128::from(10),
+                ..Default::default()
+            }
+        );
+        let record = contract.get_vote(proposal_id, validator(1)).unwrap();
+        assert_eq!(record.voter_account_id, Some(pool_owner()));
+        assert!(contract.get_commitment(proposal_id, validator(1)).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Votes must be committed and revealed for this proposal")]
+    fn test_vote_on_commit_reveal_proposal() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+        let proposal_id = create_commit_reveal_proposal(&mut contract);
+        vote_on_proposal(&mut contract, proposal_id, Vote::Yes, &validator(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Votes can only be revealed after the commit phase")]
+    fn test_reveal_during_commit_phase() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+        let proposal_id = create_commit_reveal_proposal(&mut contract);
+        commit(&mut contract, proposal_id, &validator(1), Vote::Yes, "salt");
+        contract.reveal_vote(proposal_id, validator(1), Vote::Yes, "salt".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Revealed vote does not match the commitment")]
+    fn test_reveal_different_vote() {
+        let mut context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+        let proposal_id = create_commit_reveal_proposal(&mut contract);
+        commit(&mut contract, proposal_id, &validator(1), Vote::No, "salt");
+        set_reveal_phase_context(&mut context);
+        contract.reveal_vote(proposal_id, validator(1), Vote::Yes, "salt".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Votes can only be committed during the commit phase")]
+    fn test_commit_after_commit_phase() {
+        let mut context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+        let proposal_id = create_commit_reveal_proposal(&mut contract);
+        set_reveal_phase_context(&mut context);
+        commit(&mut contract, proposal_id, &validator(1), Vote::Yes, "salt");
+    }
+
+    fn signing_key(seed: u8) -> ed25519_dalek::SigningKey {
+        ed25519_dalek::SigningKey::from_bytes(&[seed; 32])
+    }
+
+    fn public_key(signing_key: &ed25519_dalek::SigningKey) -> PublicKey {
+        let mut bytes = vec![0u8];
+        bytes.extend(signing_key.verifying_key().to_bytes());
+        PublicKey::try_from(bytes).unwrap()
+    }
+
+    fn implicit_account_id(signing_key: &ed25519_dalek::SigningKey) -> AccountId {
+        hex_encode(&signing_key.verifying_key().to_bytes())
+            .parse()
+            .unwrap()
+    }
+
+    fn signed_vote(staking_pool_id: &AccountId, vote: Vote, nonce: u64) -> SignedVote {
+        SignedVote {
+            proposal_id: 0,
+            staking_pool_id: staking_pool_id.clone(),
+            vote,
+            nonce,
+            expires_at_ms: env::block_timestamp_ms() + 1000,
+        }
+    }
+
+    /// Signs the vote as a NEP-413 message and returns the payload and the signature.
+    fn sign_vote(
+        signing_key: &ed25519_dalek::SigningKey,
+        signed_vote: &SignedVote,
+    ) -> (String, Base64VecU8) {
+        use ed25519_dalek::Signer;
+        let payload = near_sdk::serde_json::to_string(signed_vote).unwrap();
+        let recipient = voting_contract_id().to_string();
+        let mut bytes = ((1u32 << 31) + 413).to_le_bytes().to_vec();
+        bytes.extend((payload.len() as u32).to_le_bytes());
+        bytes.extend(payload.as_bytes());
+        bytes.extend([0u8; 24]);
+        bytes.extend(signed_vote.nonce.to_be_bytes());
+        bytes.extend((recipient.len() as u32).to_le_bytes());
+        bytes.extend(recipient.as_bytes());
+        bytes.push(0);
+        let signature = signing_key.sign(&env::sha256(&bytes));
+        (payload, signature.to_bytes().to_vec().into())
+    }
+
+    #[test]
+    fn test_signed_vote_by_implicit_owner() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+        let key = signing_key(1);
+        let signed_vote = signed_vote(&validator(1), Vote::Yes, 1);
+        let (payload, signature) = sign_vote(&key, &signed_vote);
+
+        let _ = contract.submit_signed_vote(payload, public_key(&key), signature);
+        contract.on_get_pool_owner_id_for_signed_vote(
+            signed_vote,
+            public_key(&key),
+            Ok(implicit_account_id(&key)),
+        );
+        let record = contract.get_vote(0, validator(1)).unwrap();
+        assert_eq!(record.choice, Vote::Yes);
+        assert_eq!(record.voter_account_id, Some(implicit_account_id(&key)));
+        assert_eq!(contract.get_signed_vote_nonce(validator(1)), 1);
+    }
+
+    #[test]
+    fn test_signed_vote_by_registered_signer() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+        let key = signing_key(1);
+        contract.on_get_pool_owner_id_for_vote_signer(
+            pool_owner(),
+            validator(1),
+            Some(public_key(&key)),
+            Ok(pool_owner()),
+        );
+        assert_eq!(
+            contract.get_vote_signer(validator(1)).unwrap().public_key,
+            public_key(&key)
+        );
+
+        let signed_vote = signed_vote(&validator(1), Vote::No, 5);
+        let (payload, signature) = sign_vote(&key, &signed_vote);
+        let _ = contract.submit_signed_vote(payload, public_key(&key), signature);
+        contract.on_get_pool_owner_id_for_signed_vote(
+            signed_vote,
+            public_key(&key),
+            Ok(pool_owner()),
+        );
+        let record = contract.get_vote(0, validator(1)).unwrap();
+        assert_eq!(record.choice, Vote::No);
+        assert_eq!(record.voter_account_id, Some(pool_owner()));
+        assert_eq!(contract.get_signed_vote_nonce(validator(1)), 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "The key is not allowed to sign votes for the staking pool")]
+    fn test_signed_vote_by_unknown_key() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+        let key = signing_key(1);
+        contract.on_get_pool_owner_id_for_signed_vote(
+            signed_vote(&validator(1), Vote::Yes, 1),
+            public_key(&key),
+            Ok(pool_owner()),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid signature")]
+    fn test_signed_vote_with_invalid_signature() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+        let key = signing_key(1);
+        let (_, signature) = sign_vote(&key, &signed_vote(&validator(1), Vote::No, 1));
+        let payload =
+            near_sdk::serde_json::to_string(&signed_vote(&validator(1), Vote::Yes, 1)).unwrap();
+        let _ = contract.submit_signed_vote(payload, public_key(&key), signature);
+    }
+
+    #[test]
+    #[should_panic(expected = "Nonce of the signed vote has already been used")]
+    fn test_signed_vote_replay() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+        let key = signing_key(1);
+        let signed_vote = signed_vote(&validator(1), Vote::Yes, 1);
+        let (payload, signature) = sign_vote(&key, &signed_vote);
+        contract.on_get_pool_owner_id_for_signed_vote(
+            signed_vote,
+            public_key(&key),
+            Ok(implicit_account_id(&key)),
+        );
+        let _ = contract.submit_signed_vote(payload, public_key(&key), signature);
+    }
+
+    #[test]
+    #[should_panic(expected = "Signed vote has expired")]
+    fn test_expired_signed_vote() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+        let key = signing_key(1);
+        let signed_vote = SignedVote {
+            expires_at_ms: env::block_timestamp_ms(),
+            ..signed_vote(&validator(1), Vote::Yes, 1)
+        };
+        let (payload, signature) = sign_vote(&key, &signed_vote);
+        let _ = contract.submit_signed_vote(payload, public_key(&key), signature);
+    }
+
+    #[test]
+    #[should_panic(expected = "user-pool is not a validator")]
+    fn test_signed_vote_for_non_validator() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+        let key = signing_key(1);
+        let (payload, signature) = sign_vote(
+            &key,
+            &signed_vote(&"user-pool".parse().unwrap(), Vote::Yes, 1),
+        );
+        let _ = contract.submit_signed_vote(payload, public_key(&key), signature);
+    }
+
+    #[test]
+    #[should_panic(expected = "user-pool is not a validator")]
+    fn test_set_vote_signer_for_non_validator() {
+        let context = get_context(&pool_owner());
+        set_context(&context);
+        let mut contract = get_contract();
+        let _ = contract.set_vote_signer("user-pool".parse().unwrap(), public_key(&signing_key(1)));
+    }
+
+    fn create_proposal_with_actions(contract: &mut Contract) -> ProposalId {
+        contract.create_proposal(
+            "Proposal with actions".to_string(),
+            Some(env::block_timestamp_ms() + 1000),
+            Some(ProposalOptions {
+                actions: vec![ProposalAction {
+                    receiver_id: accounts(4),
+                    method_name: "upgrade".to_string(),
+                    args: b"{}".to_vec().into(),
+                    deposit: NearToken::from_yoctonear(1),
+                    gas: Gas::from_tgas(50),
+                }],
+                ..Default::default()
+            }),
+        )
+    }
+
+    #[test]
+    fn test_execute() {
+        let validators = HashMap::from([(validator(1).to_string(), NearToken::from_yoctonear(10))]);
+        let context = get_context(&voting_contract_id());
+        set_context_and_validators(&context, &validators);
+        let mut contract = get_contract();
+        let proposal_id = create_proposal_with_actions(&mut contract);
+        vote_on_proposal(&mut contract, proposal_id, Vote::Yes, &validator(1));
+        assert_eq!(contract.get_status(proposal_id), ProposalStatus::Approved);
+
+        let context = get_context(&accounts(2));
+        set_context_and_validators(&context, &validators);
+        let _ = contract.execute(proposal_id);
+        assert_eq!(
+            contract.get_execution_status(proposal_id),
+            Some(ExecutionStatus::Pending)
+        );
+
+        let context = get_context(&voting_contract_id());
+        testing_env!(
+            context.build(),
+            test_vm_config(),
+            RuntimeFeesConfig::test(),
+            validators,
+            vec![PromiseResult::Successful(vec![])]
+        );
+        assert_eq!(contract.on_execute(proposal_id), ExecutionStatus::Succeeded);
+        assert_eq!(
+            contract.get_execution_status(proposal_id),
+            Some(ExecutionStatus::Succeeded)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Only approved proposals can be executed")]
+    fn test_execute_before_approval() {
+        let validators = HashMap::from([(validator(1).to_string(), NearToken::from_yoctonear(10))]);
+        let context = get_context(&voting_contract_id());
+        set_context_and_validators(&context, &validators);
+        let mut contract = get_contract();
+        let proposal_id = create_proposal_with_actions(&mut contract);
+        let _ = contract.execute(proposal_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Proposal has already been executed")]
+    fn test_execute_twice() {
+        let validators = HashMap::from([(validator(1).to_string(), NearToken::from_yoctonear(10))]);
+        let context = get_context(&voting_contract_id());
+        set_context_and_validators(&context, &validators);
+        let mut contract = get_contract();
+        let proposal_id = create_proposal_with_actions(&mut contract);
+        vote_on_proposal(&mut contract, proposal_id, Vote::Yes, &validator(1));
+        let _ = contract.execute(proposal_id);
+        let _ = contract.execute(proposal_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Total gas of the actions cannot be more than 250.0 Tgas")]
+    fn test_actions_with_too_much_gas() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+        let action = ProposalAction {
+            receiver_id: accounts(4),
+            method_name: "upgrade".to_string(),
+            args: b"{}".to_vec().into(),
+            deposit: NearToken::from_yoctonear(0),
+            gas: Gas::from_tgas(200),
+        };
+        contract.create_proposal(
+            "Proposal with actions".to_string(),
+            Some(env::block_timestamp_ms() + 1000),
+            Some(ProposalOptions {
+                actions: vec![action.clone(), action],
+                ..Default::default()
+            }),
+        );
+    }
+
+    #[test]
+    fn test_reject_when_no_reaches_one_third() {
+        let validators: HashMap<String, NearToken> = HashMap::from_iter(vec![
+            (validator(1).to_string(), NearToken::from_yoctonear(9)),
+            (validator(2).to_string(), NearToken::from_yoctonear(1)),
+            (validator(3).to_string(), NearToken::from_yoctonear(20)),
+        ]);
+        let context = get_context(&voting_contract_id());
+        set_context_and_validators(&context, &validators);
+        let mut contract = get_contract();
+        // just below 1/3 of the stake votes NO, YES can still reach 21 of 30
+        vote(&mut contract, Vote::No, &validator(1));
+        assert!(contract.get_proposal_result(0).is_none());
+        // exactly 1/3 of the stake votes NO, YES can reach 20 of 30 at most
+        vote(&mut contract, Vote::No, &validator(2));
+        let result = contract.get_proposal_result(0).unwrap();
+        assert_eq!(result.outcome, Outcome::Rejected);
+        assert_eq!(result.timestamp_ms, env::block_timestamp_ms());
+        assert_eq!(contract.get_result(0), Some(result.timestamp_ms));
+    }
+
+    #[test]
+    #[should_panic(expected = "Voting has already ended")]
+    fn test_vote_after_rejection() {
+        let validators: HashMap<String, NearToken> = HashMap::from_iter(vec![
+            (validator(1).to_string(), NearToken::from_yoctonear(20)),
+            (validator(2).to_string(), NearToken::from_yoctonear(10)),
+        ]);
+        let context = get_context(&voting_contract_id());
+        set_context_and_validators(&context, &validators);
+        let mut contract = get_contract();
+        vote(&mut contract, Vote::No, &validator(1));
+        assert_eq!(
+            contract.get_proposal_result(0).unwrap().outcome,
+            Outcome::Rejected
+        );
+        // should panic because the proposal was rejected
+        vote(&mut contract, Vote::Yes, &validator(2));
+    }
+
+    #[test]
+    fn test_proposal_status() {
+        let validators: HashMap<String, NearToken> = HashMap::from_iter(vec![
+            (validator(1).to_string(), NearToken::from_yoctonear(30)),
+            (validator(2).to_string(), NearToken::from_yoctonear(10)),
+        ]);
+        let mut context = get_context(&voting_contract_id());
+        set_context_and_validators(&context, &validators);
+        let mut contract = get_contract();
+        let rejected_id = contract.create_proposal(
+            "Rejected proposal".to_string(),
+            Some(env::block_timestamp_ms() + 1000),
+            None,
+        );
+        let expired_id = contract.create_proposal(
+            "Expired proposal".to_string(),
+            Some(env::block_timestamp_ms() + 1000),
+            None,
+        );
+        assert_eq!(contract.get_status(0), ProposalStatus::Active);
+        assert_eq!(contract.get_status(rejected_id), ProposalStatus::Active);
+        assert_eq!(contract.get_status(expired_id), ProposalStatus::Active);
+
+        vote(&mut contract, Vote::Yes, &validator(1));
+        vote_on_proposal(&mut contract, rejected_id, Vote::No, &validator(1));
+        vote_on_proposal(&mut contract, expired_id, Vote::Yes, &validator(2));
+        assert_eq!(contract.get_status(0), ProposalStatus::Approved);
+        assert_eq!(contract.get_status(rejected_id), ProposalStatus::Rejected);
+        assert_eq!(contract.get_status(expired_id), ProposalStatus::Active);
+
+        // finished proposals keep their outcome after the deadline
+        set_context_and_validators(
+            context.block_timestamp(env::block_timestamp_ms() + 2000 * 1_000_000),
+            &validators,
+        );
+        assert_eq!(contract.get_status(0), ProposalStatus::Approved);
+        assert_eq!(contract.get_status(rejected_id), ProposalStatus::Rejected);
+        assert_eq!(contract.get_status(expired_id), ProposalStatus::Expired);
+    }
+
+    #[test]
+    fn test_scheduled_start() {
+        let validators = HashMap::from([(validator(1).to_string(), NearToken::from_yoctonear(10))]);
+        let mut context = get_context(&voting_contract_id());
+        set_context_and_validators(&context, &validators);
+        let mut contract = get_contract();
+        let proposal_id = contract.create_proposal(
+            "Scheduled proposal".to_string(),
+            Some(env::block_timestamp_ms() + 1000),
+            Some(ProposalOptions {
+                start_timestamp_ms: Some(env::block_timestamp_ms() + 500),
+                ..Default::default()
+            }),
+        );
+        assert_eq!(contract.get_status(proposal_id), ProposalStatus::Pending);
+        assert_eq!(
+            contract.get_start_timestamp(proposal_id),
+            Some(env::block_timestamp_ms() + 500)
+        );
+        assert_eq!(contract.get_start_epoch_height(proposal_id), None);
+        assert!(get_logs()
+            .last()
+            .unwrap()
+            .contains("\"start_timestamp_ms\":\"500\""));
+
+        set_context_and_validators(context.block_timestamp(500 * 1_000_000), &validators);
+        assert_eq!(contract.get_status(proposal_id), ProposalStatus::Active);
+        vote_on_proposal(&mut contract, proposal_id, Vote::Yes, &validator(1));
+        assert_eq!(contract.get_status(proposal_id), ProposalStatus::Approved);
+    }
+
+    #[test]
+    #[should_panic(expected = "Voting has not started yet")]
+    fn test_vote_before_start() {
+        let validators = HashMap::from([(validator(1).to_string(), NearToken::from_yoctonear(10))]);
+        let context = get_context(&voting_contract_id());
+        set_context_and_validators(&context, &validators);
+        let mut contract = get_contract();
+        let proposal_id = contract.create_proposal(
+            "Scheduled proposal".to_string(),
+            Some(env::block_timestamp_ms() + 1000),
+            Some(ProposalOptions {
+                start_timestamp_ms: Some(env::block_timestamp_ms() + 500),
+                ..Default::default()
+            }),
+        );
+        vote_on_proposal(&mut contract, proposal_id, Vote::Yes, &validator(1));
+    }
+
+    #[test]
+    fn test_scheduled_start_epoch_height() {
+        let validators = HashMap::from([(validator(1).to_string(), NearToken::from_yoctonear(10))]);
+        let context = get_context_with_epoch_height(&voting_contract_id(), 1);
+        set_context_and_validators(&context, &validators);
+        let mut contract = get_contract();
+        let proposal_id = contract.create_proposal(
+            "Scheduled proposal".to_string(),
+            Some(env::block_timestamp_ms() + 1000),
+            Some(ProposalOptions {
+                start_epoch_height: Some(2),
+                ..Default::default()
+            }),
+        );
+        assert_eq!(contract.get_status(proposal_id), ProposalStatus::Pending);
+        assert_eq!(contract.get_start_epoch_height(proposal_id), Some(2));
+
+        let context = get_context_with_epoch_height(&voting_contract_id(), 2);
+        set_context_and_validators(&context, &validators);
+        assert_eq!(contract.get_status(proposal_id), ProposalStatus::Active);
+        assert!(contract.ping(proposal_id, None));
+    }
+
+    #[test]
+    #[should_panic(expected = "Voting has not started yet")]
+    fn test_ping_before_start() {
+        let context = get_context_with_epoch_height(&voting_contract_id(), 1);
+        set_context(&context);
+        let mut contract = get_contract();
+        let proposal_id = contract.create_proposal(
+            "Scheduled proposal".to_string(),
+            Some(env::block_timestamp_ms() + 1000),
+            Some(ProposalOptions {
+                start_epoch_height: Some(2),
+                ..Default::default()
+            }),
+        );
+        contract.ping(proposal_id, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Voting can start at a timestamp or at an epoch height, not both")]
+    fn test_start_with_timestamp_and_epoch_height() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+        contract.create_proposal(
+            "Scheduled proposal".to_string(),
+            Some(env::block_timestamp_ms() + 1000),
+            Some(ProposalOptions {
+                start_timestamp_ms: Some(env::block_timestamp_ms() + 500),
+                start_epoch_height: Some(2),
+                ..Default::default()
+            }),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Start must be before the deadline")]
+    fn test_start_after_deadline() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+        contract.create_proposal(
+            "Scheduled proposal".to_string(),
+            Some(env::block_timestamp_ms() + 1000),
+            Some(ProposalOptions {
+                start_timestamp_ms: Some(env::block_timestamp_ms() + 1000),
+                ..Default::default()
+            }),
+        );
+    }
+
+    #[test]
+    fn test_cancel_proposal() {
+        let context = get_context(&owner());
+        set_context(&context);
+        let mut contract = get_contract();
+        contract.cancel_proposal(0, "Typo in the proposal".to_string());
+        assert_eq!(contract.get_status(0), ProposalStatus::Cancelled);
+        assert_eq!(
+            contract.get_proposal_result(0).unwrap().outcome,
+            Outcome::Cancelled
+        );
+    }
+
+    fn create_scheduled_proposal(contract: &mut Contract) -> ProposalId {
+        contract.create_proposal(
+            "Scheduled proposal".to_string(),
+            Some(env::block_timestamp_ms() + 1000),
+            Some(ProposalOptions {
+                start_timestamp_ms: Some(env::block_timestamp_ms() + 500),
+                ..Default::default()
+            }),
+        )
+    }
+
+    #[test]
+    fn test_cancel_pending_proposal() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+        let proposal_id = create_scheduled_proposal(&mut contract);
+        assert_eq!(contract.get_status(proposal_id), ProposalStatus::Pending);
+
+        set_context(&get_context(&owner()));
+        contract.cancel_proposal(proposal_id, "Typo in the proposal".to_string());
+        assert_eq!(contract.get_status(proposal_id), ProposalStatus::Cancelled);
+    }
+
+    #[test]
+    #[should_panic(expected = "Proposal has been cancelled")]
+    fn test_vote_after_cancel() {
+        let context = get_context(&owner());
+        set_context(&context);
+        let mut contract = get_contract();
+        contract.cancel_proposal(0, "Typo in the proposal".to_string());
+        vote(&mut contract, Vote::Yes, &validator(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_only_owner_can_cancel() {
+        let context = get_context(&accounts(0));
+        set_context(&context);
+        let mut contract = get_contract();
+        contract.cancel_proposal(0, "Typo in the proposal".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_cancel_without_owner() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = Contract::new(
+            "Test proposal".to_string(),
+            Some(env::block_timestamp_ms() + 1000),
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        contract.cancel_proposal(0, "Typo in the proposal".to_string());
+    }
+
+    #[test]
+    fn test_extend_deadline() {
+        let mut context = get_context(&owner());
+        set_context(&context);
+        let mut contract = get_contract();
+        let new_deadline_ms = env::block_timestamp_ms() + 3000;
+        contract.extend_deadline(0, new_deadline_ms);
+        assert_eq!(contract.get_deadline_timestamp(0), Some(new_deadline_ms));
+
+        // voting is still open after the original deadline
+        set_context(context.block_timestamp(env::block_timestamp_ms() + 2000 * 1_000_000));
+        assert_eq!(contract.get_status(0), ProposalStatus::Active);
+        vote(&mut contract, Vote::Yes, &validator(0));
+    }
+
+    #[test]
+    fn test_extend_deadline_of_pending_proposal() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+        let proposal_id = create_scheduled_proposal(&mut contract);
+
+        let mut context = get_context(&owner());
+        set_context(&context);
+        let new_deadline_ms = env::block_timestamp_ms() + 3000;
+        contract.extend_deadline(proposal_id, new_deadline_ms);
+        assert_eq!(
+            contract.get_deadline_timestamp(proposal_id),
+            Some(new_deadline_ms)
+        );
+        assert_eq!(contract.get_status(proposal_id), ProposalStatus::Pending);
+
+        set_context(context.block_timestamp(2000 * 1_000_000));
+        assert_eq!(contract.get_status(proposal_id), ProposalStatus::Active);
+    }
+
+    #[test]
+    #[should_panic(expected = "New deadline must be later than the current deadline")]
+    fn test_shorten_deadline() {
+        let context = get_context(&owner());
+        set_context(&context);
+        let mut contract = get_contract();
+        contract.extend_deadline(0, env::block_timestamp_ms() + 500);
+    }
+
+    fn create_epoch_deadline_proposal(contract: &mut Contract) -> ProposalId {
+        contract.create_proposal(
+            "Epoch proposal".to_string(),
+            None,
+            Some(ProposalOptions {
+                deadline_epoch_height: Some(3),
+                ..Default::default()
+            }),
+        )
+    }
+
+    #[test]
+    fn test_epoch_deadline() {
+        let validators = HashMap::from([
+            (validator(1).to_string(), NearToken::from_yoctonear(10)),
+            (validator(2).to_string(), NearToken::from_yoctonear(30)),
+        ]);
+        let context = get_context_with_epoch_height(&voting_contract_id(), 1);
+        set_context_and_validators(&context, &validators);
+        let mut contract = get_contract();
+        let proposal_id = create_epoch_deadline_proposal(&mut contract);
+        assert_eq!(contract.get_deadline_timestamp(proposal_id), None);
+        assert_eq!(contract.get_deadline_epoch_height(proposal_id), Some(3));
+        assert_eq!(contract.get_epoch_height(), 1);
+
+        // the wall clock doesn't end the voting
+        let mut context = get_context_with_epoch_height(&voting_contract_id(), 2);
+        set_context_and_validators(
+            context.block_timestamp(env::block_timestamp_ms() + 1_000_000 * 1_000_000),
+            &validators,
+        );
+        assert_eq!(contract.get_status(proposal_id), ProposalStatus::Active);
+        vote_on_proposal(&mut contract, proposal_id, Vote::Yes, &validator(1));
+
+        let context = get_context_with_epoch_height(&voting_contract_id(), 3);
+        set_context_and_validators(&context, &validators);
+        assert_eq!(contract.get_status(proposal_id), ProposalStatus::Expired);
+    }
+
+    #[test]
+    fn test_approval_event_with_epoch_deadline() {
+        let validators = HashMap::from([(validator(1).to_string(), NearToken::from_yoctonear(10))]);
+        let context = get_context_with_epoch_height(&voting_contract_id(), 1);
+        set_context_and_validators(&context, &validators);
+        let mut contract = get_contract();
+        let proposal_id = create_epoch_deadline_proposal(&mut contract);
+        vote_on_proposal(&mut contract, proposal_id, Vote::Yes, &validator(1));
+
+        let logs = get_logs();
+        let event = logs
+            .iter()
+            .find(|log| log.contains("\"event\":\"proposal_approved\""))
+            .unwrap();
+        assert!(event.contains("\"version\":\"3.0.0\""));
+        assert!(event.contains("\"deadline_timestamp_ms\":null"));
+        assert!(event.contains("\"deadline_epoch_height\":\"3\""));
+    }
+
+    #[test]
+    #[should_panic(expected = "Voting deadline has already passed")]
+    fn test_ping_after_epoch_deadline() {
+        let context = get_context_with_epoch_height(&voting_contract_id(), 1);
+        set_context(&context);
+        let mut contract = get_contract();
+        let proposal_id = create_epoch_deadline_proposal(&mut contract);
+
+        let context = get_context_with_epoch_height(&voting_contract_id(), 3);
+        set_context(&context);
+        contract.ping(proposal_id, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Deadline can be a timestamp or an epoch height, not both")]
+    fn test_deadline_timestamp_and_epoch_height() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+        contract.create_proposal(
+            "Epoch proposal".to_string(),
+            Some(env::block_timestamp_ms() + 1000),
+            Some(ProposalOptions {
+                deadline_epoch_height: Some(3),
+                ..Default::default()
+            }),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Deadline must be a timestamp or an epoch height")]
+    fn test_without_deadline() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+        contract.create_proposal("Epoch proposal".to_string(), None, None);
+    }
+
+    #[test]
+    fn test_extend_deadline_epoch_height() {
+        let context = get_context_with_epoch_height(&owner(), 1);
+        set_context(&context);
+        let mut contract = get_contract();
+        let proposal_id = create_epoch_deadline_proposal(&mut contract);
+        contract.extend_deadline_epoch_height(proposal_id, 5);
+        assert_eq!(contract.get_deadline_epoch_height(proposal_id), Some(5));
+
+        let context = get_context_with_epoch_height(&owner(), 4);
+        set_context(&context);
+        assert_eq!(contract.get_status(proposal_id), ProposalStatus::Active);
+    }
+
+    #[test]
+    #[should_panic(expected = "Deadline of the proposal is an epoch height")]
+    fn test_extend_deadline_of_epoch_deadline_proposal() {
+        let context = get_context_with_epoch_height(&owner(), 1);
+        set_context(&context);
+        let mut contract = get_contract();
+        let proposal_id = create_epoch_deadline_proposal(&mut contract);
+        contract.extend_deadline(proposal_id, env::block_timestamp_ms() + 3000);
+    }
+
+    #[test]
+    fn test_finalize_after_epoch_change_without_ping() {
+        let mut validators = HashMap::from([
+            (validator(1).to_string(), NearToken::from_yoctonear(10)),
+            (validator(2).to_string(), NearToken::from_yoctonear(30)),
+        ]);
+        let context = get_context_with_epoch_height(&voting_contract_id(), 1);
+        set_context_and_validators(&context, &validators);
+        let mut contract = get_contract();
+        vote(&mut contract, Vote::Yes, &validator(1));
+
+        // the stake changes at epoch 3, and the proposal isn't pinged before its deadline
+        validators.insert(validator(1).to_string(), NearToken::from_yoctonear(20));
+        let mut context = get_context_with_epoch_height(&accounts(3), 3);
+        set_context_and_validators(context.block_timestamp(2000 * 1_000_000), &validators);
+        contract.finalize(0);
+        let final_tally = contract.get_final_tally(0).unwrap();
+        assert_eq!(final_tally.outcome, Outcome::Expired);
+        assert_eq!(final_tally.tally.yes, U128::from(10));
+        assert_eq!(final_tally.epoch_height, 1);
+        assert_eq!(final_tally.total_stake, U128::from(50));
+        assert!(get_logs()
+            .last()
+            .unwrap()
+            .contains("\"total_stake\":\"50\""));
+    }
+
+    fn set_after_deadline_context(validators: &HashMap<String, NearToken>) {
+        let mut context = get_context(&accounts(3));
+        set_context_and_validators(context.block_timestamp(2000 * 1_000_000), validators);
+    }
+
+    #[test]
+    fn test_finalize() {
+        let validators = HashMap::from([
+            (validator(1).to_string(), NearToken::from_yoctonear(10)),
+            (validator(2).to_string(), NearToken::from_yoctonear(30)),
+        ]);
+        let context = get_context(&voting_contract_id());
+        set_context_and_validators(&context, &validators);
+        let mut contract = get_contract();
+        vote(&mut contract, Vote::Yes, &validator(1));
+        assert_eq!(contract.get_final_tally(0), None);
+
+        set_after_deadline_context(&validators);
+        assert_eq!(contract.get_status(0), ProposalStatus::Expired);
+        contract.finalize(0);
+        assert_eq!(contract.get_status(0), ProposalStatus::Expired);
+        assert_eq!(
+            contract.get_proposal_result(0),
+            Some(ProposalResult {
+                outcome: Outcome::Expired,
+                timestamp_ms: 2000,
+            })
+        );
+        let final_tally = contract.get_final_tally(0).unwrap();
+        assert_eq!(final_tally.outcome, Outcome::Expired);
+        assert_eq!(final_tally.tally.yes, U128::from(10));
+        assert!(get_logs()
+            .last()
+            .unwrap()
+            .contains("\"event\":\"proposal_expired\""));
+        assert!(subscriber_notifications().is_empty());
+    }
+
+    #[test]
+    fn test_finalize_with_reject_on_expiry() {
+        let validators = HashMap::from([(validator(1).to_string(), NearToken::from_yoctonear(10))]);
+        let context = get_context(&owner());
+        set_context_and_validators(&context, &validators);
+        let mut contract = get_contract();
+        contract.add_subscriber(accounts(4));
+        let context = get_context(&voting_contract_id());
+        set_context_and_validators(&context, &validators);
+        let proposal_id = contract.create_proposal(
+            "Another proposal".to_string(),
+            Some(env::block_timestamp_ms() + 1000),
+            Some(ProposalOptions {
+                reject_on_expiry: true,
+                ..Default::default()
+            }),
+        );
+
+        set_after_deadline_context(&validators);
+        contract.finalize(proposal_id);
+        assert_eq!(contract.get_status(proposal_id), ProposalStatus::Rejected);
+        let logs = get_logs();
+        assert!(logs[0].contains("\"event\":\"proposal_expired\""));
+        assert!(logs[1].contains("\"event\":\"proposal_rejected\""));
+        let notifications = subscriber_notifications();
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].1["status"], "rejected");
+    }
+
+    #[test]
+    #[should_panic(expected = "Voting deadline has not passed yet")]
+    fn test_finalize_before_deadline() {
+        let context = get_context(&accounts(3));
+        set_context(&context);
+        let mut contract = get_contract();
+        contract.finalize(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Voting has already ended")]
+    fn test_finalize_twice() {
+        let validators = HashMap::from([(validator(1).to_string(), NearToken::from_yoctonear(10))]);
+        let context = get_context(&voting_contract_id());
+        set_context_and_validators(&context, &validators);
+        let mut contract = get_contract();
+        set_after_deadline_context(&validators);
+        contract.finalize(0);
+        contract.finalize(0);
+    }
+
+    #[test]
+    fn test_transfer_ownership() {
+        let new_owner = accounts(2);
+        let context = get_context(&owner());
+        set_context(&context);
+        let mut contract = get_contract();
+        contract.transfer_ownership(new_owner.clone());
+        assert_eq!(contract.get_owner_id(), Some(owner()));
+        assert_eq!(contract.get_pending_owner_id(), Some(new_owner.clone()));
+
+        let context = get_context(&new_owner);
+        set_context(&context);
+        contract.accept_ownership();
+        assert_eq!(contract.get_owner_id(), Some(new_owner));
+        assert_eq!(contract.get_pending_owner_id(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the pending owner can accept the ownership")]
+    fn test_accept_ownership_by_other_account() {
+        let context = get_context(&owner());
+        set_context(&context);
+        let mut contract = get_contract();
+        contract.transfer_ownership(accounts(2));
+
+        let context = get_context(&accounts(3));
+        set_context(&context);
+        contract.accept_ownership();
+    }
+
+    fn subscriber_notifications() -> Vec<(AccountId, serde_json::Value)> {
+        get_created_receipts()
+            .into_iter()
+            .flat_map(|receipt| {
+                let receiver_id = receipt.receiver_id.clone();
+                receipt
+                    .actions
+                    .into_iter()
+                    .filter_map(move |action| match action {
+                        MockAction::FunctionCallWeight {
+                            method_name, args, ..
+                        } if method_name == b"on_proposal_finalized" => {
+                            Some((receiver_id.clone(), serde_json::from_slice(&args).unwrap()))
+                        }
+                        _ => None,
+                    })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_notify_subscribers_on_approval() {
+        let validators = HashMap::from([(validator(1).to_string(), NearToken::from_yoctonear(10))]);
+        let context = get_context(&owner());
+        set_context_and_validators(&context, &validators);
+        let mut contract = get_contract();
+        contract.add_subscriber(accounts(4));
+        contract.add_subscriber(accounts(5));
+        assert_eq!(contract.get_subscribers(), vec![accounts(4), accounts(5)]);
+
+        let context = get_context(&voting_contract_id());
+        set_context_and_validators(&context, &validators);
+        vote(&mut contract, Vote::Yes, &validator(1));
+        let notifications = subscriber_notifications();
+        assert_eq!(notifications.len(), 2);
+        assert_eq!(notifications[0].0, accounts(4));
+        assert_eq!(notifications[1].0, accounts(5));
+        assert_eq!(
+            notifications[0].1,
+            serde_json::json!({
+                "proposal_id": 0,
+                "status": "approved",
+                "voted_stake": "10",
+                "total_stake": "10",
+            })
+        );
+    }
+
+    #[test]
+    fn test_notify_subscribers_on_rejection() {
+        let validators = HashMap::from([(validator(1).to_string(), NearToken::from_yoctonear(10))]);
+        let context = get_context(&voting_contract_id());
+        set_context_and_validators(&context, &validators);
+        let mut contract = Contract::new(
+            "Test proposal".to_string(),
+            Some(env::block_timestamp_ms() + 1000),
+            None,
+            None,
+            Some(owner()),
+            None,
+            Some(vec![accounts(4)]),
+        );
+        vote(&mut contract, Vote::No, &validator(1));
+        let notifications = subscriber_notifications();
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].1["status"], "rejected");
+        assert_eq!(notifications[0].1["voted_stake"], "10");
+    }
+
+    #[test]
+    fn test_defer_notifications_without_gas() {
+        let validators = HashMap::from([(validator(1).to_string(), NearToken::from_yoctonear(10))]);
+        let context = get_context(&owner());
+        set_context_and_validators(&context, &validators);
+        let mut contract = get_contract();
+        contract.add_subscriber(accounts(4));
+        contract.add_subscriber(accounts(5));
+
+        // 2 subscribers need 36 Tgas
+        let mut context = get_context(&voting_contract_id());
+        context.prepaid_gas(Gas::from_tgas(30));
+        set_context_and_validators(&context, &validators);
+        vote(&mut contract, Vote::Yes, &validator(1));
+        assert_eq!(contract.get_status(0), ProposalStatus::Approved);
+        assert!(subscriber_notifications().is_empty());
+        assert!(get_logs()
+            .last()
+            .unwrap()
+            .contains("\"event\":\"subscriber_notification_deferred\""));
+
+        let context = get_context(&accounts(3));
+        set_context_and_validators(&context, &validators);
+        contract.notify_subscribers(0);
+        let notifications = subscriber_notifications();
+        assert_eq!(notifications.len(), 2);
+        assert_eq!(notifications[0].1["status"], "approved");
+        assert_eq!(notifications[0].1["voted_stake"], "10");
+    }
+
+    #[test]
+    #[should_panic(expected = "No deferred notifications for the proposal")]
+    fn test_notify_subscribers_twice() {
+        let validators = HashMap::from([(validator(1).to_string(), NearToken::from_yoctonear(10))]);
+        let context = get_context(&owner());
+        set_context_and_validators(&context, &validators);
+        let mut contract = get_contract();
+        contract.add_subscriber(accounts(4));
+
+        let context = get_context(&voting_contract_id());
+        set_context_and_validators(&context, &validators);
+        vote(&mut contract, Vote::Yes, &validator(1));
+        contract.notify_subscribers(0);
+    }
+
+    #[test]
+    fn test_remove_subscriber() {
+        let validators = HashMap::from([(validator(1).to_string(), NearToken::from_yoctonear(10))]);
+        let context = get_context(&owner());
+        set_context_and_validators(&context, &validators);
+        let mut contract = get_contract();
+        contract.add_subscriber(accounts(4));
+        contract.remove_subscriber(accounts(4));
+        assert!(contract.get_subscribers().is_empty());
+
+        let context = get_context(&voting_contract_id());
+        set_context_and_validators(&context, &validators);
+        vote(&mut contract, Vote::Yes, &validator(1));
+        assert!(subscriber_notifications().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_add_subscriber_by_non_owner() {
+        let context = get_context(&accounts(3));
+        set_context(&context);
+        let mut contract = get_contract();
+        contract.add_subscriber(accounts(4));
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot have more than 5 subscribers")]
+    fn test_too_many_subscribers() {
+        let context = get_context(&owner());
+        set_context(&context);
+        let mut contract = get_contract();
+        for i in 0..=MAX_SUBSCRIBERS {
+            contract.add_subscriber(format!("subscriber-{i}.near").parse().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_subscriber_notification_failed() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+        contract.on_notify_subscriber(0, accounts(4), Err(PromiseError::Failed));
+        assert!(get_logs()
+            .last()
+            .unwrap()
+            .contains("\"event\":\"subscriber_notification_failed\""));
+    }
+
+    #[test]
+    fn test_final_tally() {
+        let validators = HashMap::from([
+            (validator(1).to_string(), NearToken::from_yoctonear(30)),
+            (validator(2).to_string(), NearToken::from_yoctonear(10)),
+        ]);
+        let context = get_context(&voting_contract_id());
+        set_context_and_validators(&context, &validators);
+        let mut contract = get_contract();
+        vote(&mut contract, Vote::No, &validator(2));
+        assert!(!contract.is_approved(0));
+        assert_eq!(contract.get_final_tally(0), None);
+
+        vote(&mut contract, Vote::Yes, &validator(1));
+        assert!(contract.is_approved(0));
+        let final_tally = FinalTally {
+            outcome: Outcome::Approved,
+            voted_stake: U128::from(30),
+            tally: contract.get_tally(0),
+            total_stake: U128::from(40),
+            threshold: config::DEFAULT_THRESHOLD,
+            num_votes: 2,
+            epoch_height: 0,
+            timestamp_ms: env::block_timestamp_ms(),
+        };
+        assert_eq!(contract.get_final_tally(0), Some(final_tally.clone()));
+
+        // the final tally doesn't follow later stake changes
+        let validators = HashMap::from([
+            (validator(1).to_string(), NearToken::from_yoctonear(30)),
+            (validator(2).to_string(), NearToken::from_yoctonear(100)),
+        ]);
+        let context = get_context_with_epoch_height(&voting_contract_id(), 1);
+        set_context_and_validators(&context, &validators);
+        assert_eq!(contract.get_final_tally(0), Some(final_tally));
+        assert_eq!(
+            contract.get_total_voted_stake(0),
+            (U128::from(30), U128::from(40))
+        );
+    }
+
+    #[test]
+    fn test_final_tally_of_cancelled_proposal() {
+        let validators = HashMap::from([
+            (validator(1).to_string(), NearToken::from_yoctonear(10)),
+            (validator(2).to_string(), NearToken::from_yoctonear(30)),
+        ]);
+        let context = get_context(&owner());
+        set_context_and_validators(&context, &validators);
+        let mut contract = get_contract();
+        vote(&mut contract, Vote::Yes, &validator(1));
+        contract.cancel_proposal(0, "Typo in the proposal".to_string());
+        let final_tally = contract.get_final_tally(0).unwrap();
+        assert_eq!(final_tally.outcome, Outcome::Cancelled);
+        assert_eq!(final_tally.voted_stake, U128::from(10));
+        assert_eq!(final_tally.total_stake, U128::from(40));
+        assert_eq!(final_tally.num_votes, 1);
+    }
+
+    #[test]
+    fn test_voting_metadata() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let contract = get_contract_with_config(
+            None,
+            Some(Ratio {
+                numerator: 3,
+                denominator: 4,
+            }),
+        );
+        let metadata = contract.voting_metadata();
+        assert_eq!(metadata.standard, "validator-voting");
+        assert_eq!(metadata.version, "1.0.0");
+        assert_eq!(metadata.threshold, config::DEFAULT_THRESHOLD);
+        assert_eq!(
+            metadata.quorum,
+            Some(Ratio {
+                numerator: 3,
+                denominator: 4
+            })
+        );
+    }
+
+    #[test]
+    fn test_migrate_from_single_proposal() {
+        let validators: HashMap<String, NearToken> = HashMap::from_iter(vec![
+            (validator(1).to_string(), NearToken::from_yoctonear(10)),
+            (validator(2).to_string(), NearToken::from_yoctonear(20)),
+            (validator(3).to_string(), NearToken::from_yoctonear(30)),
+        ]);
+        let context = get_context_with_epoch_height(&voting_contract_id(), 1);
+        set_context_and_validators(&context, &validators);
+        env::state_write(&migration::ContractV0 {
+            proposal: "Test proposal".to_string(),
+            deadline_timestamp_ms: env::block_timestamp_ms() + 1000,
+            votes: HashMap::from_iter(vec![(validator(2), 20), (validator(1), 10)]),
+            total_voted_stake: 30,
+            result: None,
+            last_epoch_height: 1,
+        });
+
+        let mut contract = Contract::migrate_from_single_proposal(Some(owner()));
+        assert_eq!(contract.get_num_proposals(), 1);
+        assert_eq!(contract.get_proposal(0), "Test proposal");
+        assert_eq!(contract.get_status(0), ProposalStatus::Active);
+        assert_eq!(contract.get_owner_id(), Some(owner()));
+        assert_eq!(contract.get_tally(0).yes, U128::from(30));
+        let votes = contract.get_votes(0);
+        assert_eq!(votes.len(), 2);
+        assert_eq!(votes[&validator(1)].choice, Vote::Yes);
+        assert_eq!(votes[&validator(2)].stake, U128::from(20));
+        assert_eq!(votes[&validator(2)].voter_account_id, None);
+
+        // voting continues on the migrated proposal
+        vote(&mut contract, Vote::Yes, &validator(3));
+        assert_eq!(contract.get_status(0), ProposalStatus::Approved);
+    }
+
+    #[test]
+    fn test_upgrade() {
+        let context = get_context(&owner());
+        set_context(&context);
+        let mut contract = get_contract();
+        let _ = contract.upgrade(vec![1, 2, 3]);
+
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].receiver_id, voting_contract_id());
+        assert!(matches!(
+            &receipts[0].actions[0],
+            MockAction::DeployContract { code, .. } if code == &vec![1, 2, 3]
+        ));
+        assert!(matches!(
+            &receipts[0].actions[1],
+            MockAction::FunctionCallWeight { method_name, .. } if method_name == b"migrate"
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_upgrade_by_non_owner() {
+        let context = get_context(&accounts(3));
+        set_context(&context);
+        let mut contract = get_contract();
+        let _ = contract.upgrade(vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_migrate() {
+        let validators = HashMap::from([
+            (validator(1).to_string(), NearToken::from_yoctonear(10)),
+            (validator(2).to_string(), NearToken::from_yoctonear(10)),
+        ]);
+        let context = get_context(&voting_contract_id());
+        set_context_and_validators(&context, &validators);
+        let mut contract = get_contract();
+        vote(&mut contract, Vote::Yes, &validator(1));
+        vote(&mut contract, Vote::No, &validator(2));
+        env::state_write(&contract);
+        drop(contract);
+
+        let contract = Contract::migrate();
+        assert_eq!(contract.get_state_version(), StateVersion::V2);
+        assert_eq!(contract.get_owner_id(), Some(owner()));
+        assert_eq!(contract.get_num_votes(0), 2);
+        assert_eq!(
+            contract.get_vote(0, validator(1)).unwrap().choice,
+            Vote::Yes
+        );
+        assert_eq!(contract.get_tally(0).no, U128::from(10));
+        assert_eq!(contract.get_status(0), ProposalStatus::Rejected);
+        assert!(get_logs()
+            .last()
+            .unwrap()
+            .contains("\"event\":\"contract_migrated\""));
+    }
+
+    #[test]
+    fn test_migrate_state_v1() {
+        let validators = HashMap::from([(validator(1).to_string(), NearToken::from_yoctonear(10))]);
+        let context = get_context(&voting_contract_id());
+        set_context_and_validators(&context, &validators);
+        let mut contract = get_contract();
+        vote(&mut contract, Vote::Yes, &validator(1));
+        contract.proposals.flush();
+        let contract = migration::ContractV1 {
+            version: StateVersion::V1,
+            owner_id: contract.owner_id,
+            pending_owner_id: contract.pending_owner_id,
+            config: contract.config,
+            proposals: contract.proposals,
+            num_proposals: contract.num_proposals,
+            voters: contract.voters,
+            vote_signers: contract.vote_signers,
+            signed_vote_nonces: contract.signed_vote_nonces,
+            subscribers: contract.subscribers,
+        };
+        env::state_write(&contract);
+        drop(contract);
+
+        let contract = Contract::migrate();
+        assert_eq!(contract.get_state_version(), StateVersion::V2);
+        assert_eq!(contract.get_owner_id(), Some(owner()));
+        assert_eq!(contract.get_status(0), ProposalStatus::Approved);
+        assert!(!contract.pending_notifications.contains(&0));
+    }
+
+    /// Store a proposal in the given layout, and return its storage key.
+    fn write_proposal(
+        proposal_id: ProposalId,
+        version: migration::ProposalVersion,
+        proposal: &impl borsh::BorshSerialize,
+    ) -> Vec<u8> {
+        let key = borsh::to_vec(&(StorageKey::Proposals, proposal_id)).unwrap();
+        env::storage_write(&key, &borsh::to_vec(&(version, proposal)).unwrap());
+        key
+    }
+
+    #[test]
+    fn test_migrate_proposal_v1() {
+        let validators = HashMap::from([
+            (validator(1).to_string(), NearToken::from_yoctonear(10)),
+            (validator(2).to_string(), NearToken::from_yoctonear(10)),
+        ]);
+        let context = get_context(&voting_contract_id());
+        set_context_and_validators(&context, &validators);
+        let contract = get_contract();
+        env::state_write(&contract);
+        drop(contract);
+
+        // replace proposal 0 with one stored in the first layout
+        let deadline_timestamp_ms = env::block_timestamp_ms() + 1000;
+        let mut votes = IterableMap::new(StorageKey::Votes { proposal_id: 0 });
+        votes.insert(
+            validator(1),
+            VoteRecord {
+                choice: Vote::Yes,
+                stake: U128::from(10),
+                voter_account_id: Some(pool_owner()),
+                voted_at_ms: 0,
+                voted_epoch: 0,
+                last_updated_epoch: 0,
+            },
+        );
+        let proposal = migration::ProposalV1 {
+            description: "Old proposal".to_string(),
+            deadline_timestamp_ms,
+            votes,
+            tally: Tally {
+                yes: U128::from(10),
+                ..Default::default()
+            },
+            result: None,
+            last_epoch_height: 0,
+            pending_ping: None,
+            commit_deadline_timestamp_ms: None,
+            commitments: LookupMap::new(StorageKey::Commitments { proposal_id: 0 }),
+            actions: vec![],
+            execution_status: None,
+        };
+        let key = write_proposal(0, migration::ProposalVersion::V1, &proposal);
+        drop(proposal);
+
+        let mut contract = Contract::migrate();
+        assert_eq!(contract.get_proposal(0), "Old proposal");
+        assert_eq!(
+            contract.get_deadline_timestamp(0),
+            Some(deadline_timestamp_ms)
+        );
+        assert_eq!(contract.get_tally(0).yes, U128::from(10));
+        assert_eq!(contract.get_num_votes(0), 1);
+        assert_eq!(contract.get_status(0), ProposalStatus::Active);
+
+        // voting continues, and the proposal is written back in the current layout
+        vote(&mut contract, Vote::Yes, &validator(2));
+        assert_eq!(contract.get_status(0), ProposalStatus::Approved);
+        env::state_write(&contract);
+        drop(contract);
+        assert_eq!(
+            env::storage_read(&key).unwrap()[0],
+            borsh::to_vec(&migration::CURRENT_PROPOSAL_VERSION).unwrap()[0]
+        );
+    }
+
+    #[test]
+    fn test_migrate_proposal_v2() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let contract = get_contract();
+        env::state_write(&contract);
+        drop(contract);
+
+        let proposal = migration::ProposalV2 {
+            description: "Old proposal".to_string(),
+            deadline_timestamp_ms: env::block_timestamp_ms() + 1000,
+            votes: IterableMap::new(StorageKey::Votes { proposal_id: 0 }),
+            tally: Tally::default(),
+            result: None,
+            last_epoch_height: 0,
+            pending_ping: None,
+            commit_deadline_timestamp_ms: None,
+            commitments: LookupMap::new(StorageKey::Commitments { proposal_id: 0 }),
+            actions: vec![],
+            execution_status: None,
+            metadata: Some(proposal_metadata()),
+        };
+        write_proposal(0, migration::ProposalVersion::V2, &proposal);
+        drop(proposal);
+
+        let contract = Contract::migrate();
+        assert_eq!(contract.get_proposal(0), "Old proposal");
+        assert_eq!(contract.get_proposal_metadata(0), Some(proposal_metadata()));
+        assert_eq!(contract.get_status(0), ProposalStatus::Active);
+    }
+
+    #[test]
+    fn test_migrate_proposal_v3() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let contract = get_contract();
+        env::state_write(&contract);
+        drop(contract);
+
+        let start_timestamp_ms = env::block_timestamp_ms() + 100;
+        let proposal = migration::ProposalV3 {
+            description: "Old proposal".to_string(),
+            deadline_timestamp_ms: env::block_timestamp_ms() + 1000,
+            votes: IterableMap::new(StorageKey::Votes { proposal_id: 0 }),
+            tally: Tally::default(),
+            result: None,
+            last_epoch_height: 0,
+            pending_ping: None,
+            commit_deadline_timestamp_ms: None,
+            commitments: LookupMap::new(StorageKey::Commitments { proposal_id: 0 }),
+            actions: vec![],
+            execution_status: None,
+            metadata: None,
+            start_timestamp_ms: Some(start_timestamp_ms),
+            start_epoch_height: None,
+        };
+        write_proposal(0, migration::ProposalVersion::V3, &proposal);
+        drop(proposal);
+
+        let contract = Contract::migrate();
+        assert_eq!(contract.get_start_timestamp(0), Some(start_timestamp_ms));
+        assert_eq!(contract.get_start_epoch_height(0), None);
+        assert_eq!(contract.get_status(0), ProposalStatus::Pending);
+    }
+
+    #[test]
+    fn test_migrate_proposal_v4() {
+        let context = get_context_with_epoch_height(&voting_contract_id(), 1);
+        set_context(&context);
+        let contract = get_contract();
+        env::state_write(&contract);
+        drop(contract);
+
+        let proposal = migration::ProposalV4 {
+            description: "Old proposal".to_string(),
+            deadline_timestamp_ms: None,
+            votes: IterableMap::new(StorageKey::Votes { proposal_id: 0 }),
+            tally: Tally::default(),
+            result: None,
+            last_epoch_height: 0,
+            pending_ping: None,
+            commit_deadline_timestamp_ms: None,
+            commitments: LookupMap::new(StorageKey::Commitments { proposal_id: 0 }),
+            actions: vec![],
+            execution_status: None,
+            metadata: None,
+            start_timestamp_ms: None,
+            start_epoch_height: None,
+            deadline_epoch_height: Some(3),
+        };
+        write_proposal(0, migration::ProposalVersion::V4, &proposal);
+        drop(proposal);
+
+        let contract = Contract::migrate();
+        assert_eq!(contract.get_deadline_timestamp(0), None);
+        assert_eq!(contract.get_deadline_epoch_height(0), Some(3));
+        assert_eq!(contract.get_status(0), ProposalStatus::Active);
+
+        let context = get_context_with_epoch_height(&voting_contract_id(), 3);
+        set_context(&context);
+        assert_eq!(contract.get_status(0), ProposalStatus::Expired);
+    }
+
+    #[test]
+    fn test_migrate_proposal_v5() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let contract = get_contract();
+        env::state_write(&contract);
+        drop(contract);
+
+        let proposal = migration::ProposalV5 {
+            description: "Old proposal".to_string(),
+            deadline_timestamp_ms: Some(env::block_timestamp_ms() + 1000),
+            votes: IterableMap::new(StorageKey::Votes { proposal_id: 0 }),
+            tally: Tally::default(),
+            result: None,
+            last_epoch_height: 0,
+            pending_ping: None,
+            commit_deadline_timestamp_ms: None,
+            commitments: LookupMap::new(StorageKey::Commitments { proposal_id: 0 }),
+            actions: vec![],
+            execution_status: None,
+            metadata: None,
+            start_timestamp_ms: None,
+            start_epoch_height: None,
+            deadline_epoch_height: None,
+            reject_on_expiry: true,
+        };
+        write_proposal(0, migration::ProposalVersion::V5, &proposal);
+        drop(proposal);
+
+        let mut contract = Contract::migrate();
+        set_after_deadline_context(&validators());
+        contract.finalize(0);
+        assert_eq!(contract.get_status(0), ProposalStatus::Rejected);
+    }
+
+    #[test]
+    fn test_migrate_ended_proposal_v5() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let contract = get_contract();
+        env::state_write(&contract);
+        drop(contract);
+
+        let proposal = migration::ProposalV5 {
+            description: "Old proposal".to_string(),
+            deadline_timestamp_ms: Some(env::block_timestamp_ms() + 1000),
+            votes: IterableMap::new(StorageKey::Votes { proposal_id: 0 }),
+            tally: Tally {
+                yes: U128::from(2500),
+                ..Default::default()
+            },
+            result: Some(ProposalResult {
+                outcome: Outcome::Approved,
+                timestamp_ms: env::block_timestamp_ms(),
+            }),
+            last_epoch_height: 0,
+            pending_ping: None,
+            commit_deadline_timestamp_ms: None,
+            commitments: LookupMap::new(StorageKey::Commitments { proposal_id: 0 }),
+            actions: vec![],
+            execution_status: None,
+            metadata: None,
+            start_timestamp_ms: None,
+            start_epoch_height: None,
+            deadline_epoch_height: None,
+            reject_on_expiry: false,
+        };
+        write_proposal(0, migration::ProposalVersion::V5, &proposal);
+        drop(proposal);
+
+        let contract = Contract::migrate();
+        assert!(contract.is_approved(0));
+        assert_eq!(contract.get_final_tally(0), None);
+        assert_eq!(
+            contract.get_total_voted_stake(0),
+            (U128::from(2500), U128::from(3000))
+        );
+    }
+
+    #[test]
+    fn test_validator_kick_out() {
+        let mut validators: HashMap<String, NearToken> = HashMap::from_iter(vec![
+            (validator(1).to_string(), NearToken::from_yoctonear(40)),
+            (validator(2).to_string(), NearToken::from_yoctonear(10)),
+            (validator(3).to_string(), NearToken::from_yoctonear(10)),
+        ]);
+        let context = get_context_with_epoch_height(&voting_contract_id(), 1);
+        set_context_and_validators(&context, &validators);
+        let mut contract = get_contract();
+        // vote at epoch 1
+        vote(&mut contract, Vote::Yes, &validator(1));
+        assert_eq!((contract.get_total_voted_stake(0).0).0, 40);
+        assert_eq!(contract.get_votes(0).len(), 1);
+        // remove validator at epoch 2
+        validators.remove(&validator(1).to_string());
+        let context = get_context_with_epoch_height(&voting_contract_id(), 2);
+        set_context_and_validators(&context, &validators);
+        // ping will update total voted stake
+        contract.ping(0, None);
+        assert_eq!((contract.get_total_voted_stake(0).0).0, 0);
+        assert_eq!(contract.get_votes(0).len(), 1);
+        // validator(1) is back to validator set at epoch 3
+        validators.insert(validator(1).to_string(), NearToken::from_yoctonear(40));
+        let context = get_context_with_epoch_height(&voting_contract_id(), 3);
+        set_context_and_validators(&context, &validators);
+        // ping will update total voted stake after validator(1) is back
+        contract.ping(0, None);
+        assert_eq!((contract.get_total_voted_stake(0).0).0, 40);
+        assert_eq!(contract.get_votes(0).len(), 1);
+    }
+
+    #[test]
+    fn test_init_contract() {
+        let contract = get_contract();
+        assert_eq!(contract.get_proposal(0), "Test proposal");
+        assert_eq!(
+            contract.get_config(),
+            Config {
+                threshold: Ratio {
+                    numerator: 2,
+                    denominator: 3,
+                },
+                quorum: None,
+            }
+        );
+        assert_eq!(
+            contract.get_deadline_timestamp(0),
+            Some(env::block_timestamp_ms() + 1000)
+        );
+    }
+
+    #[test]
+    fn test_create_proposal() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+        assert_eq!(contract.get_num_proposals(), 1);
+
+        let proposal_id = contract.create_proposal(
+            "Another proposal".to_string(),
+            Some(env::block_timestamp_ms() + 2000),
+            None,
+        );
+        assert_eq!(proposal_id, 1);
+        assert_eq!(contract.get_num_proposals(), 2);
+        assert_eq!(contract.get_proposal(0), "Test proposal");
+        assert_eq!(contract.get_proposal(1), "Another proposal");
+        assert_eq!(
+            contract.get_deadline_timestamp(1),
+            Some(env::block_timestamp_ms() + 2000)
+        );
+    }
+
+    #[test]
+    fn test_votes_are_separated_by_proposal() {
+        let validators = HashMap::from_iter(vec![
+            (validator(0).to_string(), NearToken::from_yoctonear(100)),
+            (validator(1).to_string(), NearToken::from_yoctonear(10)),
+        ]);
+        let context = get_context(&voting_contract_id());
+        set_context_and_validators(&context, &validators);
+        let mut contract = get_contract();
+        let proposal_id = contract.create_proposal(
+            "Another proposal".to_string(),
+            Some(env::block_timestamp_ms() + 1000),
+            None,
+        );
+
+        // approve the second proposal only
+        vote_on_proposal(&mut contract, proposal_id, Vote::Yes, &validator(0));
+        assert_eq!(
+            contract.get_proposal_result(proposal_id).unwrap().outcome,
+            Outcome::Approved
+        );
+        assert_eq!(contract.get_votes(proposal_id).len(), 1);
+        assert!(contract.get_result(0).is_none());
+        assert!(contract.get_votes(0).is_empty());
+        assert_eq!((contract.get_total_voted_stake(0).0).0, 0);
+
+        // the first proposal is still open for voting
+        vote(&mut contract, Vote::Yes, &validator(1));
+        assert_eq!(contract.get_votes(0).len(), 1);
+        assert!(contract.get_result(0).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Proposal not found")]
+    fn test_vote_on_unknown_proposal() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+        vote_on_proposal(&mut contract, 1, Vote::Yes, &validator(0));
+    }
+
+    fn proposal_metadata() -> ProposalMetadata {
+        ProposalMetadata {
+            title: "Enable feature".to_string(),
+            description: "Enable the feature in the next protocol version".to_string(),
+            url: Some("https://example.com/proposal.md".to_string()),
+            content_hash: Some(hex_encode(&env::sha256(b"proposal"))),
+            category: Some("protocol".to_string()),
+            proposer: Some(accounts(3)),
+        }
+    }
+
+    #[test]
+    fn test_init_with_metadata() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let contract = Contract::new(
+            "Test proposal".to_string(),
+            Some(env::block_timestamp_ms() + 1000),
+            None,
+            None,
+            None,
+            Some(ProposalOptions {
+                metadata: Some(proposal_metadata()),
+                ..Default::default()
+            }),
+            None,
+        );
+        assert_eq!(contract.get_proposal(0), "Test proposal");
+        assert_eq!(contract.get_proposal_metadata(0), Some(proposal_metadata()));
+    }
+
+    #[test]
+    fn test_proposal_without_metadata() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let contract = get_contract();
+        assert_eq!(contract.get_proposal_metadata(0), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Title cannot be empty")]
+    fn test_metadata_with_empty_title() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+        contract.create_proposal(
+            "Another proposal".to_string(),
+            Some(env::block_timestamp_ms() + 1000),
+            Some(ProposalOptions {
+                metadata: Some(ProposalMetadata {
+                    title: "".to_string(),
+                    ..proposal_metadata()
+                }),
+                ..Default::default()
+            }),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Title cannot be longer than 200 bytes")]
+    fn test_metadata_with_long_title() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+        contract.create_proposal(
+            "Another proposal".to_string(),
+            Some(env::block_timestamp_ms() + 1000),
+            Some(ProposalOptions {
+                metadata: Some(ProposalMetadata {
+                    title: "a".repeat(201),
+                    ..proposal_metadata()
+                }),
+                ..Default::default()
+            }),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Content hash must be a hex encoded sha256 hash")]
+    fn test_metadata_with_invalid_content_hash() {
+        let context = get_context(&voting_contract_id());
+        set_context(&context);
+        let mut contract = get_contract();
+        contract.create_proposal(
+            "Another proposal".to_string(),
+            Some(env::block_timestamp_ms() + 1000),
+            Some(ProposalOptions {
+                metadata: Some(ProposalMetadata {
+                    content_hash: Some("not a hash".to_string()),
+                    ..proposal_metadata()
+                }),
+                ..Default::default()
+            }),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Proposal cannot be empty")]
+    fn test_init_with_empty_proposal() {
+        let context = VMContextBuilder::new();
+        set_context(&context);
+        Contract::new(
+            "".to_string(),
+            Some(env::block_timestamp_ms() + 1000),
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Deadline must be in the future")]
+    fn test_init_with_past_deadline() {
+        let context = VMContextBuilder::new();
+        set_context(&context);
+        Contract::new(
+            "Test proposal".to_string(),
+            Some(env::block_timestamp_ms()),
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Threshold must be between 0 and 1")]
+    fn test_init_with_invalid_threshold() {
+        let context = VMContextBuilder::new();
+        set_context(&context);
+        get_contract_with_config(
+            Some(Ratio {
+                numerator: 3,
+                denominator: 3,
+            }),
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Quorum must be between 0 and 1")]
+    fn test_init_with_invalid_quorum() {
+        let context = VMContextBuilder::new();
+        set_context(&context);
+        get_contract_with_config(
+            None,
+            Some(Ratio {
+                numerator: 0,
+                denominator: 2,
+            }),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Quorum must be greater than the threshold")]
+    fn test_init_with_quorum_below_threshold() {
+        let context = VMContextBuilder::new();
+        set_context(&context);
+        get_contract_with_config(
+            None,
+            Some(Ratio {
+                numerator: 2,
+                denominator: 3,
+            }),
+        );
+    }
+
+    #[test]
+    fn test_custom_threshold() {
+        let validators: HashMap<String, NearToken> = HashMap::from_iter(vec![
+            (validator(1).to_string(), NearToken::from_yoctonear(70)),
+            (validator(2).to_string(), NearToken::from_yoctonear(10)),
+            (validator(3).to_string(), NearToken::from_yoctonear(20)),
+        ]);
+        let context = get_context(&voting_contract_id());
+        set_context_and_validators(&context, &validators);
+        let threshold = Ratio {
+            numerator: 3,
+            denominator: 4,
+        };
+        let mut contract = get_contract_with_config(Some(threshold), None);
+        assert_eq!(contract.get_config().threshold, threshold);
+        let proposal_id = contract.create_proposal(
+            "Another proposal".to_string(),
+            Some(env::block_timestamp_ms() + 1000),
+            None,
+        );
+
+        // 70% is not enough for a 3/4 threshold
+        vote(&mut contract, Vote::Yes, &validator(1));
+        assert_eq!(contract.get_status(0), ProposalStatus::Active);
+        vote(&mut contract, Vote::Yes, &validator(2));
+        assert_eq!(contract.get_status(0), ProposalStatus::Approved);
+
+        // more than 1/4 voting NO makes 3/4 unreachable
+        vote_on_proposal(&mut contract, proposal_id, Vote::No, &validator(3));
+        assert_eq!(contract.get_status(proposal_id), ProposalStatus::Active);
+        vote_on_proposal(&mut contract, proposal_id, Vote::No, &validator(2));
+        assert_eq!(contract.get_status(proposal_id), ProposalStatus::Rejected);
+    }
+
+    #[test]
+    fn test_quorum() {
+        let validators: HashMap<String, NearToken> = HashMap::from_iter(vec![
+            (validator(1).to_string(), NearToken::from_yoctonear(40)),
+            (validator(2).to_string(), NearToken::from_yoctonear(40)),
+            (validator(3).to_string(), NearToken::from_yoctonear(20)),
+        ]);
+        let context = get_context(&voting_contract_id());
+        set_context_and_validators(&context, &validators);
+        let quorum = Ratio {
+            numerator: 3,
+            denominator: 4,
+        };
+        let mut contract = get_contract_with_config(
+            Some(Ratio {
+                numerator: 1,
+                denominator: 3,
+            }),
+            Some(quorum),
+        );
+        assert_eq!(contract.get_config().quorum, Some(quorum));
+
+        // the threshold is reached but only 40% of the stake voted
+        vote(&mut contract, Vote::Yes, &validator(1));
+        assert_eq!(contract.get_status(0), ProposalStatus::Active);
+        // abstentions count towards the quorum
+        vote(&mut contract, Vote::Abstain, &validator(2));
+        assert_eq!(contract.get_status(0), ProposalStatus::Approved);
+    }
+
+    #[test]
+    fn test_ratio_apply() {
+        let two_thirds = Ratio {
+            numerator: 2,
+            denominator: 3,
+        };
+        assert_eq!(two_thirds.apply(3000), 2000);
+        assert_eq!(two_thirds.apply(10), 6);
+        // no overflow on large amounts
+        assert_eq!(two_thirds.apply(u128::MAX), u128::MAX / 3 * 2);
+        assert_eq!(two_thirds.complement().apply(3000), 1000);
     }
 
     #[test]
@@ -558,6 +3187,6 @@ mod tests {
                 .block_timestamp(env::block_timestamp_ms() + 2000 * 1_000_000)
                 .epoch_height(2),
         );
-        contract.ping();
+        contract.ping(0, None);
     }
 }
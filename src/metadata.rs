@@ -0,0 +1,60 @@
+use crate::*;
+
+pub const MAX_TITLE_LENGTH: usize = 200;
+pub const MAX_DESCRIPTION_LENGTH: usize = 10_000;
+pub const MAX_URL_LENGTH: usize = 2_000;
+pub const MAX_CATEGORY_LENGTH: usize = 64;
+
+/// Structured description of a proposal, for proposals whose full text lives off-chain.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProposalMetadata {
+    pub title: String,
+    pub description: String,
+    /// Link to the full document of the proposal.
+    pub url: Option<String>,
+    /// Hex encoded sha256 hash of the document at `url`.
+    pub content_hash: Option<String>,
+    pub category: Option<String>,
+    /// Account that submitted the proposal.
+    pub proposer: Option<AccountId>,
+}
+
+impl ProposalMetadata {
+    /// Checks the length of each field and the format of the content hash.
+    pub fn assert_valid(&self) {
+        require!(!self.title.is_empty(), "Title cannot be empty");
+        require!(
+            self.title.len() <= MAX_TITLE_LENGTH,
+            format!("Title cannot be longer than {} bytes", MAX_TITLE_LENGTH)
+        );
+        require!(
+            self.description.len() <= MAX_DESCRIPTION_LENGTH,
+            format!(
+                "Description cannot be longer than {} bytes",
+                MAX_DESCRIPTION_LENGTH
+            )
+        );
+        if let Some(url) = &self.url {
+            require!(
+                !url.is_empty() && url.len() <= MAX_URL_LENGTH,
+                format!("Url must be between 1 and {} bytes", MAX_URL_LENGTH)
+            );
+        }
+        if let Some(content_hash) = &self.content_hash {
+            require!(
+                is_sha256_hex(content_hash),
+                "Content hash must be a hex encoded sha256 hash"
+            );
+        }
+        if let Some(category) = &self.category {
+            require!(
+                !category.is_empty() && category.len() <= MAX_CATEGORY_LENGTH,
+                format!(
+                    "Category must be between 1 and {} bytes",
+                    MAX_CATEGORY_LENGTH
+                )
+            );
+        }
+    }
+}
@@ -0,0 +1,437 @@
+use crate::*;
+use near_sdk::borsh::{self, io, BorshDeserialize, BorshSerialize};
+use near_sdk::{GasWeight, NearToken};
+
+/// Version of the layout of the contract state. Stored as the first field of the state, so
+/// `migrate` can tell which layout it reads. A new variant is added whenever an upgrade changes
+/// the layout, along with a copy of the previous layout to convert it from.
+#[near(serializers = [borsh, json])]
+#[serde(rename_all = "lowercase")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StateVersion {
+    V1,
+    V2,
+}
+
+/// Version of the layout written by this code.
+pub const CURRENT_STATE_VERSION: StateVersion = StateVersion::V2;
+
+/// Version of the layout of a stored proposal. Proposals are kept in a `LookupMap`, which
+/// `migrate` can't iterate, so each proposal is stored with its version and converted to the
+/// current layout when it is read. A new variant is added whenever an upgrade changes the layout
+/// of `Proposal`, along with a copy of the previous layout to convert it from.
+#[near(serializers = [borsh])]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ProposalVersion {
+    V1,
+    V2,
+    V3,
+    V4,
+    V5,
+    V6,
+}
+
+/// Version of the proposal layout written by this code.
+pub const CURRENT_PROPOSAL_VERSION: ProposalVersion = ProposalVersion::V6;
+
+/// Gas reserved for `upgrade` itself. The rest of the attached gas goes to `migrate`.
+const UPGRADE_GAS: Gas = Gas::from_tgas(10);
+
+/// State of the single proposal contract, before proposals were kept in a registry.
+#[near(serializers = [borsh])]
+pub struct ContractV0 {
+    pub proposal: String,
+    pub deadline_timestamp_ms: Timestamp,
+    pub votes: HashMap<AccountId, Balance>,
+    pub total_voted_stake: Balance,
+    pub result: Option<Timestamp>,
+    pub last_epoch_height: EpochHeight,
+}
+
+/// State of the first upgradable version of the contract, before notifications could be
+/// deferred.
+#[near(serializers = [borsh])]
+pub struct ContractV1 {
+    pub version: StateVersion,
+    pub owner_id: Option<AccountId>,
+    pub pending_owner_id: Option<AccountId>,
+    pub config: Config,
+    pub proposals: LookupMap<ProposalId, VersionedProposal>,
+    pub num_proposals: u64,
+    pub voters: LookupMap<AccountId, Delegation>,
+    pub vote_signers: LookupMap<AccountId, VoteSigner>,
+    pub signed_vote_nonces: LookupMap<AccountId, u64>,
+    pub subscribers: IterableSet<AccountId>,
+}
+
+impl From<ContractV1> for Contract {
+    fn from(contract: ContractV1) -> Self {
+        Self {
+            version: CURRENT_STATE_VERSION,
+            owner_id: contract.owner_id,
+            pending_owner_id: contract.pending_owner_id,
+            config: contract.config,
+            proposals: contract.proposals,
+            num_proposals: contract.num_proposals,
+            voters: contract.voters,
+            vote_signers: contract.vote_signers,
+            signed_vote_nonces: contract.signed_vote_nonces,
+            subscribers: contract.subscribers,
+            pending_notifications: LookupSet::new(StorageKey::PendingNotifications),
+        }
+    }
+}
+
+/// Layout of a proposal stored by the first upgradable version of the contract.
+#[near(serializers = [borsh])]
+pub struct ProposalV1 {
+    pub description: String,
+    pub deadline_timestamp_ms: Timestamp,
+    pub votes: IterableMap<AccountId, VoteRecord>,
+    pub tally: Tally,
+    pub result: Option<ProposalResult>,
+    pub last_epoch_height: EpochHeight,
+    pub pending_ping: Option<PendingPing>,
+    pub commit_deadline_timestamp_ms: Option<Timestamp>,
+    pub commitments: LookupMap<AccountId, Commitment>,
+    pub actions: Vec<ProposalAction>,
+    pub execution_status: Option<ExecutionStatus>,
+}
+
+impl From<ProposalV1> for Proposal {
+    fn from(proposal: ProposalV1) -> Self {
+        ProposalV2 {
+            description: proposal.description,
+            deadline_timestamp_ms: proposal.deadline_timestamp_ms,
+            votes: proposal.votes,
+            tally: proposal.tally,
+            result: proposal.result,
+            last_epoch_height: proposal.last_epoch_height,
+            pending_ping: proposal.pending_ping,
+            commit_deadline_timestamp_ms: proposal.commit_deadline_timestamp_ms,
+            commitments: proposal.commitments,
+            actions: proposal.actions,
+            execution_status: proposal.execution_status,
+            metadata: None,
+        }
+        .into()
+    }
+}
+
+/// Layout of a proposal once it got structured metadata.
+#[near(serializers = [borsh])]
+pub struct ProposalV2 {
+    pub description: String,
+    pub deadline_timestamp_ms: Timestamp,
+    pub votes: IterableMap<AccountId, VoteRecord>,
+    pub tally: Tally,
+    pub result: Option<ProposalResult>,
+    pub last_epoch_height: EpochHeight,
+    pub pending_ping: Option<PendingPing>,
+    pub commit_deadline_timestamp_ms: Option<Timestamp>,
+    pub commitments: LookupMap<AccountId, Commitment>,
+    pub actions: Vec<ProposalAction>,
+    pub execution_status: Option<ExecutionStatus>,
+    pub metadata: Option<ProposalMetadata>,
+}
+
+impl From<ProposalV2> for Proposal {
+    fn from(proposal: ProposalV2) -> Self {
+        ProposalV3 {
+            description: proposal.description,
+            deadline_timestamp_ms: proposal.deadline_timestamp_ms,
+            votes: proposal.votes,
+            tally: proposal.tally,
+            result: proposal.result,
+            last_epoch_height: proposal.last_epoch_height,
+            pending_ping: proposal.pending_ping,
+            commit_deadline_timestamp_ms: proposal.commit_deadline_timestamp_ms,
+            commitments: proposal.commitments,
+            actions: proposal.actions,
+            execution_status: proposal.execution_status,
+            metadata: proposal.metadata,
+            start_timestamp_ms: None,
+            start_epoch_height: None,
+        }
+        .into()
+    }
+}
+
+/// Layout of a proposal once the start of voting could be scheduled.
+#[near(serializers = [borsh])]
+pub struct ProposalV3 {
+    pub description: String,
+    pub deadline_timestamp_ms: Timestamp,
+    pub votes: IterableMap<AccountId, VoteRecord>,
+    pub tally: Tally,
+    pub result: Option<ProposalResult>,
+    pub last_epoch_height: EpochHeight,
+    pub pending_ping: Option<PendingPing>,
+    pub commit_deadline_timestamp_ms: Option<Timestamp>,
+    pub commitments: LookupMap<AccountId, Commitment>,
+    pub actions: Vec<ProposalAction>,
+    pub execution_status: Option<ExecutionStatus>,
+    pub metadata: Option<ProposalMetadata>,
+    pub start_timestamp_ms: Option<Timestamp>,
+    pub start_epoch_height: Option<EpochHeight>,
+}
+
+impl From<ProposalV3> for Proposal {
+    fn from(proposal: ProposalV3) -> Self {
+        ProposalV4 {
+            description: proposal.description,
+            deadline_timestamp_ms: Some(proposal.deadline_timestamp_ms),
+            votes: proposal.votes,
+            tally: proposal.tally,
+            result: proposal.result,
+            last_epoch_height: proposal.last_epoch_height,
+            pending_ping: proposal.pending_ping,
+            commit_deadline_timestamp_ms: proposal.commit_deadline_timestamp_ms,
+            commitments: proposal.commitments,
+            actions: proposal.actions,
+            execution_status: proposal.execution_status,
+            metadata: proposal.metadata,
+            start_timestamp_ms: proposal.start_timestamp_ms,
+            start_epoch_height: proposal.start_epoch_height,
+            deadline_epoch_height: None,
+        }
+        .into()
+    }
+}
+
+/// Layout of a proposal once the deadline could be an epoch height.
+#[near(serializers = [borsh])]
+pub struct ProposalV4 {
+    pub description: String,
+    pub deadline_timestamp_ms: Option<Timestamp>,
+    pub votes: IterableMap<AccountId, VoteRecord>,
+    pub tally: Tally,
+    pub result: Option<ProposalResult>,
+    pub last_epoch_height: EpochHeight,
+    pub pending_ping: Option<PendingPing>,
+    pub commit_deadline_timestamp_ms: Option<Timestamp>,
+    pub commitments: LookupMap<AccountId, Commitment>,
+    pub actions: Vec<ProposalAction>,
+    pub execution_status: Option<ExecutionStatus>,
+    pub metadata: Option<ProposalMetadata>,
+    pub start_timestamp_ms: Option<Timestamp>,
+    pub start_epoch_height: Option<EpochHeight>,
+    pub deadline_epoch_height: Option<EpochHeight>,
+}
+
+impl From<ProposalV4> for Proposal {
+    fn from(proposal: ProposalV4) -> Self {
+        ProposalV5 {
+            description: proposal.description,
+            deadline_timestamp_ms: proposal.deadline_timestamp_ms,
+            votes: proposal.votes,
+            tally: proposal.tally,
+            result: proposal.result,
+            last_epoch_height: proposal.last_epoch_height,
+            pending_ping: proposal.pending_ping,
+            commit_deadline_timestamp_ms: proposal.commit_deadline_timestamp_ms,
+            commitments: proposal.commitments,
+            actions: proposal.actions,
+            execution_status: proposal.execution_status,
+            metadata: proposal.metadata,
+            start_timestamp_ms: proposal.start_timestamp_ms,
+            start_epoch_height: proposal.start_epoch_height,
+            deadline_epoch_height: proposal.deadline_epoch_height,
+            reject_on_expiry: false,
+        }
+        .into()
+    }
+}
+
+/// Layout of a proposal once it could be rejected on expiry.
+#[near(serializers = [borsh])]
+pub struct ProposalV5 {
+    pub description: String,
+    pub deadline_timestamp_ms: Option<Timestamp>,
+    pub votes: IterableMap<AccountId, VoteRecord>,
+    pub tally: Tally,
+    pub result: Option<ProposalResult>,
+    pub last_epoch_height: EpochHeight,
+    pub pending_ping: Option<PendingPing>,
+    pub commit_deadline_timestamp_ms: Option<Timestamp>,
+    pub commitments: LookupMap<AccountId, Commitment>,
+    pub actions: Vec<ProposalAction>,
+    pub execution_status: Option<ExecutionStatus>,
+    pub metadata: Option<ProposalMetadata>,
+    pub start_timestamp_ms: Option<Timestamp>,
+    pub start_epoch_height: Option<EpochHeight>,
+    pub deadline_epoch_height: Option<EpochHeight>,
+    pub reject_on_expiry: bool,
+}
+
+impl From<ProposalV5> for Proposal {
+    fn from(proposal: ProposalV5) -> Self {
+        Self {
+            description: proposal.description,
+            deadline_timestamp_ms: proposal.deadline_timestamp_ms,
+            votes: proposal.votes,
+            tally: proposal.tally,
+            result: proposal.result,
+            last_epoch_height: proposal.last_epoch_height,
+            pending_ping: proposal.pending_ping,
+            commit_deadline_timestamp_ms: proposal.commit_deadline_timestamp_ms,
+            commitments: proposal.commitments,
+            actions: proposal.actions,
+            execution_status: proposal.execution_status,
+            metadata: proposal.metadata,
+            start_timestamp_ms: proposal.start_timestamp_ms,
+            start_epoch_height: proposal.start_epoch_height,
+            deadline_epoch_height: proposal.deadline_epoch_height,
+            reject_on_expiry: proposal.reject_on_expiry,
+            // the total stake a proposal that already ended was decided with wasn't kept
+            final_tally: None,
+        }
+    }
+}
+
+/// A proposal as stored in the registry, preceded by the version of its layout. Proposals of an
+/// earlier version are converted to the current layout when they are read, and written back in
+/// it once they are updated.
+pub struct VersionedProposal(pub Proposal);
+
+impl BorshSerialize for VersionedProposal {
+    fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        CURRENT_PROPOSAL_VERSION.serialize(writer)?;
+        self.0.serialize(writer)
+    }
+}
+
+impl BorshDeserialize for VersionedProposal {
+    fn deserialize_reader<R: io::Read>(reader: &mut R) -> io::Result<Self> {
+        let proposal = match ProposalVersion::deserialize_reader(reader)? {
+            ProposalVersion::V1 => ProposalV1::deserialize_reader(reader)?.into(),
+            ProposalVersion::V2 => ProposalV2::deserialize_reader(reader)?.into(),
+            ProposalVersion::V3 => ProposalV3::deserialize_reader(reader)?.into(),
+            ProposalVersion::V4 => ProposalV4::deserialize_reader(reader)?.into(),
+            ProposalVersion::V5 => ProposalV5::deserialize_reader(reader)?.into(),
+            ProposalVersion::V6 => Proposal::deserialize_reader(reader)?,
+        };
+        Ok(Self(proposal))
+    }
+}
+
+#[near]
+impl Contract {
+    /// Deploy new code to this contract and call `migrate` to convert the state. The code is the
+    /// borsh encoded bytes of the wasm. Owner only. All the attached gas, except what the call
+    /// itself uses, goes to `migrate`.
+    pub fn upgrade(&mut self, #[serializer(borsh)] code: Vec<u8>) -> Promise {
+        self.assert_owner();
+        require!(
+            env::prepaid_gas() > UPGRADE_GAS,
+            format!("Upgrade requires more than {} of gas", UPGRADE_GAS)
+        );
+        Promise::new(env::current_account_id())
+            .deploy_contract(code)
+            .function_call_weight(
+                "migrate".to_string(),
+                b"{}".to_vec(),
+                NearToken::from_yoctonear(0),
+                Gas::from_gas(0),
+                GasWeight(1),
+            )
+    }
+
+    /// Convert the state written by any earlier version of the code to the current layout.
+    /// Called by `upgrade` after deploying the new code.
+    #[init(ignore_state)]
+    #[private]
+    pub fn migrate() -> Self {
+        let state = env::storage_read(b"STATE")
+            .unwrap_or_else(|| env::panic_str("Contract state not found"));
+        let version = StateVersion::deserialize(&mut state.as_slice())
+            .unwrap_or_else(|_| env::panic_str("Unknown state version"));
+        let contract = match version {
+            StateVersion::V1 => borsh::from_slice::<ContractV1>(&state)
+                .unwrap_or_else(|_| env::panic_str("Failed to read the contract state"))
+                .into(),
+            StateVersion::V2 => borsh::from_slice::<Contract>(&state)
+                .unwrap_or_else(|_| env::panic_str("Failed to read the contract state")),
+        };
+        Event::ContractMigrated {
+            from_version: &version,
+            to_version: &CURRENT_STATE_VERSION,
+        }
+        .emit();
+        contract
+    }
+
+    /// Returns the version of the layout of the contract state.
+    pub fn get_state_version(&self) -> StateVersion {
+        self.version
+    }
+
+    /// Migrate the state of a single proposal contract after deploying this version of the code.
+    /// The proposal becomes proposal `0` and keeps its votes, which were all `Yes` votes.
+    #[init(ignore_state)]
+    #[private]
+    pub fn migrate_from_single_proposal(owner_id: Option<AccountId>) -> Self {
+        let old_state: ContractV0 =
+            env::state_read().unwrap_or_else(|| env::panic_str("Old state not found"));
+
+        // insert in a deterministic order since the old votes were unordered
+        let mut old_votes: Vec<(AccountId, Balance)> = old_state.votes.into_iter().collect();
+        old_votes.sort();
+        let mut votes = IterableMap::new(StorageKey::Votes { proposal_id: 0 });
+        for (account_id, stake) in old_votes {
+            votes.insert(
+                account_id,
+                VoteRecord {
+                    choice: Vote::Yes,
+                    stake: stake.into(),
+                    voter_account_id: None,
+                    voted_at_ms: 0,
+                    voted_epoch: 0,
+                    last_updated_epoch: old_state.last_epoch_height,
+                },
+            );
+        }
+
+        let proposal = Proposal {
+            description: old_state.proposal,
+            deadline_timestamp_ms: Some(old_state.deadline_timestamp_ms),
+            votes,
+            tally: Tally {
+                yes: old_state.total_voted_stake.into(),
+                ..Default::default()
+            },
+            result: old_state.result.map(|timestamp_ms| ProposalResult {
+                outcome: Outcome::Approved,
+                timestamp_ms,
+            }),
+            last_epoch_height: old_state.last_epoch_height,
+            pending_ping: None,
+            commit_deadline_timestamp_ms: None,
+            commitments: LookupMap::new(StorageKey::Commitments { proposal_id: 0 }),
+            actions: vec![],
+            execution_status: None,
+            metadata: None,
+            start_timestamp_ms: None,
+            start_epoch_height: None,
+            deadline_epoch_height: None,
+            reject_on_expiry: false,
+            final_tally: None,
+        };
+        let mut proposals = LookupMap::new(StorageKey::Proposals);
+        proposals.insert(0, VersionedProposal(proposal));
+
+        Self {
+            version: CURRENT_STATE_VERSION,
+            owner_id,
+            pending_owner_id: None,
+            config: Config::new(None, None),
+            proposals,
+            num_proposals: 1,
+            voters: LookupMap::new(StorageKey::Voters),
+            vote_signers: LookupMap::new(StorageKey::VoteSigners),
+            signed_vote_nonces: LookupMap::new(StorageKey::SignedVoteNonces),
+            subscribers: IterableSet::new(StorageKey::Subscribers),
+            pending_notifications: LookupSet::new(StorageKey::PendingNotifications),
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use crate::*;
+
+/// Owner methods
+#[near]
+impl Contract {
+    /// Cancel a pending or active proposal. No more votes are accepted for it.
+    pub fn cancel_proposal(&mut self, proposal_id: ProposalId, reason: String) {
+        self.assert_owner();
+        let config = self.config.clone();
+        let proposal = self.internal_get_proposal_mut(proposal_id);
+        proposal.assert_pending_or_active();
+        proposal.set_result(Outcome::Cancelled, &config, validator_total_stake());
+        Event::ProposalCancelled {
+            proposal_id: &proposal_id,
+            reason: &reason,
+            cancellation_timestamp_ms: &U64::from(env::block_timestamp_ms()),
+        }
+        .emit();
+    }
+
+    /// Move the deadline timestamp of a pending or active proposal further into the future.
+    pub fn extend_deadline(&mut self, proposal_id: ProposalId, new_deadline_ms: Timestamp) {
+        self.assert_owner();
+        let proposal = self.internal_get_proposal_mut(proposal_id);
+        proposal.assert_pending_or_active();
+        let old_deadline_ms = proposal
+            .deadline_timestamp_ms
+            .unwrap_or_else(|| env::panic_str("Deadline of the proposal is an epoch height"));
+        require!(
+            new_deadline_ms > old_deadline_ms,
+            "New deadline must be later than the current deadline"
+        );
+        proposal.deadline_timestamp_ms = Some(new_deadline_ms);
+        Event::DeadlineExtended {
+            proposal_id: &proposal_id,
+            old_deadline_timestamp_ms: &U64::from(old_deadline_ms),
+            new_deadline_timestamp_ms: &U64::from(new_deadline_ms),
+        }
+        .emit();
+    }
+
+    /// Move the deadline epoch height of a pending or active proposal further into the future.
+    pub fn extend_deadline_epoch_height(
+        &mut self,
+        proposal_id: ProposalId,
+        new_deadline_epoch_height: EpochHeight,
+    ) {
+        self.assert_owner();
+        let proposal = self.internal_get_proposal_mut(proposal_id);
+        proposal.assert_pending_or_active();
+        let old_deadline_epoch_height = proposal
+            .deadline_epoch_height
+            .unwrap_or_else(|| env::panic_str("Deadline of the proposal is a timestamp"));
+        require!(
+            new_deadline_epoch_height > old_deadline_epoch_height,
+            "New deadline must be later than the current deadline"
+        );
+        proposal.deadline_epoch_height = Some(new_deadline_epoch_height);
+        Event::DeadlineEpochHeightExtended {
+            proposal_id: &proposal_id,
+            old_deadline_epoch_height: &U64::from(old_deadline_epoch_height),
+            new_deadline_epoch_height: &U64::from(new_deadline_epoch_height),
+        }
+        .emit();
+    }
+
+    /// Start transferring the ownership. The new owner needs to call `accept_ownership` to
+    /// complete the transfer.
+    pub fn transfer_ownership(&mut self, new_owner_id: AccountId) {
+        self.assert_owner();
+        Event::OwnershipTransferStarted {
+            owner_id: self.owner_id.as_ref().unwrap(),
+            pending_owner_id: &new_owner_id,
+        }
+        .emit();
+        self.pending_owner_id = Some(new_owner_id);
+    }
+
+    /// Complete the ownership transfer. Called by the pending owner.
+    pub fn accept_ownership(&mut self) {
+        let new_owner_id = env::predecessor_account_id();
+        require!(
+            self.pending_owner_id.as_ref() == Some(&new_owner_id),
+            "Only the pending owner can accept the ownership"
+        );
+        let old_owner_id = self.owner_id.replace(new_owner_id.clone());
+        self.pending_owner_id = None;
+        Event::OwnershipTransferred {
+            old_owner_id: &old_owner_id,
+            new_owner_id: &new_owner_id,
+        }
+        .emit();
+    }
+
+    /// Returns the owner of the contract, if any.
+    pub fn get_owner_id(&self) -> Option<AccountId> {
+        self.owner_id.clone()
+    }
+
+    /// Returns the account the ownership is being transferred to, if any.
+    pub fn get_pending_owner_id(&self) -> Option<AccountId> {
+        self.pending_owner_id.clone()
+    }
+}
+
+impl Contract {
+    pub(crate) fn assert_owner(&self) {
+        require!(
+            self.owner_id.as_ref() == Some(&env::predecessor_account_id()),
+            "Only the owner can call this method"
+        );
+    }
+}
@@ -0,0 +1,577 @@
+use crate::*;
+
+/// Maximum number of votes refreshed by a single `ping` when no limit is given.
+pub const DEFAULT_PING_LIMIT: u32 = 100;
+
+/// A hidden vote of a validator, the hex encoded sha256 hash of the vote and a salt.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Commitment {
+    pub hash: String,
+    pub voter_account_id: AccountId,
+    pub committed_at_ms: Timestamp,
+}
+
+/// Returns the commitment of a vote, the hex encoded sha256 hash of the vote as `yes`, `no` or
+/// `abstain` followed by the salt.
+pub fn vote_commitment(vote: &Vote, salt: &str) -> String {
+    let vote = match vote {
+        Vote::Yes => "yes",
+        Vote::No => "no",
+        Vote::Abstain => "abstain",
+    };
+    hex_encode(&env::sha256(format!("{}{}", vote, salt).as_bytes()))
+}
+
+/// The choice of a validator, the stake it currently counts with and when it was cast.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, PartialEq)]
+pub struct VoteRecord {
+    pub choice: Vote,
+    pub stake: U128,
+    /// Account that cast the vote on behalf of the staking pool. `None` for votes migrated from
+    /// the single proposal contract, which didn't keep it.
+    pub voter_account_id: Option<AccountId>,
+    /// Timestamp of the latest vote in milliseconds.
+    pub voted_at_ms: Timestamp,
+    /// Epoch height of the latest vote.
+    pub voted_epoch: EpochHeight,
+    /// Epoch height of the stake refresh that counted this vote in the tally.
+    pub last_updated_epoch: EpochHeight,
+}
+
+/// A recomputation of the tally for a new epoch that has not covered all votes yet.
+#[near(serializers = [borsh])]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PendingPing {
+    pub epoch_height: EpochHeight,
+    /// Number of votes, in iteration order, already counted in `tally`.
+    pub cursor: u32,
+    pub tally: Tally,
+}
+
+/// The outcome of a proposal and the timestamp of when it was reached.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProposalResult {
+    pub outcome: Outcome,
+    pub timestamp_ms: Timestamp,
+}
+
+/// Optional settings of a proposal.
+#[near(serializers = [json])]
+#[serde(default)]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProposalOptions {
+    /// Enables commit-reveal voting. Validators commit their votes until this timestamp and
+    /// reveal them until the deadline of the proposal.
+    pub commit_deadline_timestamp_ms: Option<Timestamp>,
+    /// Function calls that anyone can dispatch with `execute` once the proposal is approved.
+    pub actions: Vec<ProposalAction>,
+    /// Structured description of the proposal.
+    pub metadata: Option<ProposalMetadata>,
+    /// Voting opens at this timestamp instead of at creation.
+    pub start_timestamp_ms: Option<Timestamp>,
+    /// Voting opens at this epoch height instead of at creation. Cannot be combined with
+    /// `start_timestamp_ms`.
+    pub start_epoch_height: Option<EpochHeight>,
+    /// Voting ends at this epoch height. Replaces the deadline timestamp, which must not be
+    /// given.
+    pub deadline_epoch_height: Option<EpochHeight>,
+    /// `finalize` rejects the proposal instead of recording it as expired.
+    pub reject_on_expiry: bool,
+}
+
+/// A single proposal voted on by validators. Once the majority of the stake holders agree to
+/// the proposal, or enough stake opposes it that it can no longer be approved, the outcome and
+/// time will be recorded and the voting for this proposal ends.
+#[near(serializers = [borsh])]
+pub struct Proposal {
+    pub description: String,
+    /// Exactly one of `deadline_timestamp_ms` and `deadline_epoch_height` is set.
+    pub deadline_timestamp_ms: Option<Timestamp>,
+    pub votes: IterableMap<AccountId, VoteRecord>,
+    pub tally: Tally,
+    pub result: Option<ProposalResult>,
+    pub last_epoch_height: EpochHeight,
+    pub pending_ping: Option<PendingPing>,
+    /// End of the commit phase if the proposal uses commit-reveal voting.
+    pub commit_deadline_timestamp_ms: Option<Timestamp>,
+    /// Votes committed but not revealed yet.
+    pub commitments: LookupMap<AccountId, Commitment>,
+    pub actions: Vec<ProposalAction>,
+    pub execution_status: Option<ExecutionStatus>,
+    pub metadata: Option<ProposalMetadata>,
+    pub start_timestamp_ms: Option<Timestamp>,
+    pub start_epoch_height: Option<EpochHeight>,
+    pub deadline_epoch_height: Option<EpochHeight>,
+    pub reject_on_expiry: bool,
+    /// The tally the outcome was decided with, set when the voting ends.
+    pub final_tally: Option<FinalTally>,
+}
+
+impl Proposal {
+    pub fn new(
+        proposal_id: ProposalId,
+        description: String,
+        deadline_timestamp_ms: Option<Timestamp>,
+        options: ProposalOptions,
+    ) -> Self {
+        require!(!description.is_empty(), "Proposal cannot be empty");
+        match (deadline_timestamp_ms, options.deadline_epoch_height) {
+            (Some(deadline_timestamp_ms), None) => require!(
+                deadline_timestamp_ms > env::block_timestamp_ms(),
+                "Deadline must be in the future"
+            ),
+            (None, Some(deadline_epoch_height)) => require!(
+                deadline_epoch_height > env::epoch_height(),
+                "Deadline epoch height must be in the future"
+            ),
+            (None, None) => env::panic_str("Deadline must be a timestamp or an epoch height"),
+            (Some(_), Some(_)) => {
+                env::panic_str("Deadline can be a timestamp or an epoch height, not both")
+            }
+        }
+        require!(
+            options.start_timestamp_ms.is_none() || options.start_epoch_height.is_none(),
+            "Voting can start at a timestamp or at an epoch height, not both"
+        );
+        if let (Some(start_timestamp_ms), Some(deadline_timestamp_ms)) =
+            (options.start_timestamp_ms, deadline_timestamp_ms)
+        {
+            require!(
+                start_timestamp_ms < deadline_timestamp_ms,
+                "Start must be before the deadline"
+            );
+        }
+        if let (Some(start_epoch_height), Some(deadline_epoch_height)) =
+            (options.start_epoch_height, options.deadline_epoch_height)
+        {
+            require!(
+                start_epoch_height < deadline_epoch_height,
+                "Start must be before the deadline"
+            );
+        }
+        if let Some(commit_deadline_timestamp_ms) = options.commit_deadline_timestamp_ms {
+            require!(
+                commit_deadline_timestamp_ms > env::block_timestamp_ms()
+                    && deadline_timestamp_ms.is_none_or(|deadline_timestamp_ms| {
+                        commit_deadline_timestamp_ms < deadline_timestamp_ms
+                    }),
+                "Commit deadline must be in the future and before the deadline"
+            );
+            require!(
+                options.start_timestamp_ms.is_none_or(|start_timestamp_ms| {
+                    commit_deadline_timestamp_ms > start_timestamp_ms
+                }),
+                "Commit deadline must be after the start"
+            );
+        }
+        assert_valid_actions(&options.actions);
+        if let Some(metadata) = &options.metadata {
+            metadata.assert_valid();
+        }
+        Self {
+            description,
+            deadline_timestamp_ms,
+            votes: IterableMap::new(StorageKey::Votes { proposal_id }),
+            tally: Tally::default(),
+            result: None,
+            last_epoch_height: 0,
+            pending_ping: None,
+            commit_deadline_timestamp_ms: options.commit_deadline_timestamp_ms,
+            commitments: LookupMap::new(StorageKey::Commitments { proposal_id }),
+            actions: options.actions,
+            execution_status: None,
+            metadata: options.metadata,
+            start_timestamp_ms: options.start_timestamp_ms,
+            start_epoch_height: options.start_epoch_height,
+            deadline_epoch_height: options.deadline_epoch_height,
+            reject_on_expiry: options.reject_on_expiry,
+            final_tally: None,
+        }
+    }
+
+    /// Returns the current state of the proposal.
+    pub fn status(&self) -> ProposalStatus {
+        match &self.result {
+            Some(result) => result.outcome.into(),
+            None if self.is_past_deadline() => ProposalStatus::Expired,
+            None if !self.has_started() => ProposalStatus::Pending,
+            None => ProposalStatus::Active,
+        }
+    }
+
+    /// Whether the deadline, a timestamp or an epoch height, has been reached.
+    pub fn is_past_deadline(&self) -> bool {
+        self.deadline_timestamp_ms
+            .is_some_and(|deadline_timestamp_ms| env::block_timestamp_ms() >= deadline_timestamp_ms)
+            || self
+                .deadline_epoch_height
+                .is_some_and(|deadline_epoch_height| env::epoch_height() >= deadline_epoch_height)
+    }
+
+    /// Whether the start timestamp or epoch height, if any, has been reached.
+    pub fn has_started(&self) -> bool {
+        self.start_timestamp_ms
+            .is_none_or(|start_timestamp_ms| env::block_timestamp_ms() >= start_timestamp_ms)
+            && self
+                .start_epoch_height
+                .is_none_or(|start_epoch_height| env::epoch_height() >= start_epoch_height)
+    }
+
+    /// Panics unless the proposal is open for voting.
+    pub fn assert_active(&self) {
+        if let Err(error) = self.check_active() {
+            env::panic_str(error);
+        }
+    }
+
+    /// Returns why the proposal is not open for voting, if it isn't.
+    pub fn check_active(&self) -> Result<(), &'static str> {
+        match self.status() {
+            ProposalStatus::Active => Ok(()),
+            ProposalStatus::Pending => Err("Voting has not started yet"),
+            ProposalStatus::Expired => Err("Voting deadline has already passed"),
+            ProposalStatus::Cancelled => Err("Proposal has been cancelled"),
+            ProposalStatus::Approved | ProposalStatus::Rejected => Err("Voting has already ended"),
+        }
+    }
+
+    /// Panics unless the proposal is open for voting or scheduled to open later.
+    pub fn assert_pending_or_active(&self) {
+        if self.status() != ProposalStatus::Pending {
+            self.assert_active();
+        }
+    }
+
+    /// Update the votes according to current stake of validators. After an epoch change at most
+    /// `limit` votes are refreshed per call, and the new tally only takes effect once all votes
+    /// have been refreshed. Returns whether the tally is up to date with the current epoch.
+    pub fn ping(&mut self, proposal_id: ProposalId, config: &Config, limit: u32) -> bool {
+        self.assert_active();
+        let cur_epoch_height = env::epoch_height();
+        if cur_epoch_height == self.last_epoch_height {
+            return true;
+        }
+        // restart the recomputation if the epoch changed again in the middle of it
+        let mut pending = match self.pending_ping.take() {
+            Some(pending) if pending.epoch_height == cur_epoch_height => pending,
+            _ => PendingPing {
+                epoch_height: cur_epoch_height,
+                cursor: 0,
+                tally: Tally::default(),
+            },
+        };
+        for (account_id, record) in self
+            .votes
+            .iter_mut()
+            .skip(pending.cursor as usize)
+            .take(limit as usize)
+        {
+            if record.last_updated_epoch != cur_epoch_height {
+                record.stake = validator_stake(account_id).into();
+                record.last_updated_epoch = cur_epoch_height;
+            }
+            pending.tally.add(&record.choice, record.stake.0);
+            pending.cursor += 1;
+        }
+        if pending.cursor < self.votes.len() {
+            self.pending_ping = Some(pending);
+            return false;
+        }
+        self.tally = pending.tally;
+        self.last_epoch_height = cur_epoch_height;
+        self.check_result(proposal_id, config);
+        true
+    }
+
+    /// Whether the votes are committed and revealed instead of cast directly.
+    pub fn is_commit_reveal(&self) -> bool {
+        self.commit_deadline_timestamp_ms.is_some()
+    }
+
+    /// Whether the proposal uses commit-reveal voting and its commit phase has not ended.
+    pub fn is_commit_phase(&self) -> bool {
+        self.commit_deadline_timestamp_ms
+            .is_some_and(|commit_deadline_ms| env::block_timestamp_ms() < commit_deadline_ms)
+    }
+
+    /// Cast the vote of the given validator. Not allowed with commit-reveal voting.
+    pub fn vote(
+        &mut self,
+        proposal_id: ProposalId,
+        config: &Config,
+        vote: Vote,
+        account_id: AccountId,
+        voter_account_id: AccountId,
+        ping_limit: u32,
+    ) {
+        require!(
+            !self.is_commit_reveal(),
+            "Votes must be committed and revealed for this proposal"
+        );
+        self.record_vote(
+            proposal_id,
+            config,
+            vote,
+            account_id,
+            voter_account_id,
+            ping_limit,
+        );
+    }
+
+    /// Commit the hidden vote of the given validator during the commit phase. A previous
+    /// commitment of the validator is replaced.
+    pub fn commit(
+        &mut self,
+        proposal_id: ProposalId,
+        account_id: AccountId,
+        voter_account_id: AccountId,
+        commitment: String,
+    ) {
+        self.assert_active();
+        require!(
+            self.is_commit_phase(),
+            "Votes can only be committed during the commit phase"
+        );
+        require!(
+            is_sha256_hex(&commitment),
+            "Commitment must be a hex encoded sha256 hash"
+        );
+        require!(
+            validator_stake(&account_id) > 0,
+            format!("{} is not a validator", account_id)
+        );
+        let commitment = commitment.to_ascii_lowercase();
+        Event::VoteCommitted {
+            proposal_id: &proposal_id,
+            validator_id: &account_id,
+            commitment: &commitment,
+        }
+        .emit();
+        self.commitments.insert(
+            account_id,
+            Commitment {
+                hash: commitment,
+                voter_account_id,
+                committed_at_ms: env::block_timestamp_ms(),
+            },
+        );
+    }
+
+    /// Reveal the committed vote of the given validator after the commit phase. The vote then
+    /// counts like a vote cast directly.
+    pub fn reveal(
+        &mut self,
+        proposal_id: ProposalId,
+        config: &Config,
+        account_id: AccountId,
+        vote: Vote,
+        salt: String,
+    ) {
+        self.assert_active();
+        require!(
+            self.is_commit_reveal() && !self.is_commit_phase(),
+            "Votes can only be revealed after the commit phase"
+        );
+        let commitment = self
+            .commitments
+            .remove(&account_id)
+            .unwrap_or_else(|| env::panic_str("No committed vote to reveal"));
+        require!(
+            commitment.hash == vote_commitment(&vote, &salt),
+            "Revealed vote does not match the commitment"
+        );
+        Event::VoteRevealed {
+            proposal_id: &proposal_id,
+            validator_id: &account_id,
+            vote: &vote,
+        }
+        .emit();
+        self.record_vote(
+            proposal_id,
+            config,
+            vote,
+            account_id,
+            commitment.voter_account_id,
+            DEFAULT_PING_LIMIT,
+        );
+    }
+
+    /// Record the vote of the given validator, cast by `voter_account_id`, after a `ping` that
+    /// refreshes at most `ping_limit` votes. A previous vote of the validator is replaced. While
+    /// the tally is being recomputed for a new epoch, the vote only counts towards the new tally
+    /// and the result is checked once the recomputation completes.
+    fn record_vote(
+        &mut self,
+        proposal_id: ProposalId,
+        config: &Config,
+        vote: Vote,
+        account_id: AccountId,
+        voter_account_id: AccountId,
+        ping_limit: u32,
+    ) {
+        self.ping(proposal_id, config, ping_limit);
+
+        let stake = validator_stake(&account_id);
+        require!(stake > 0, format!("{} is not a validator", account_id));
+
+        let old_record = self.votes.get(&account_id).cloned();
+        let last_updated_epoch = match self.pending_ping.as_mut() {
+            Some(pending) => {
+                // a vote that is already counted in the new tally is updated there, otherwise
+                // the recomputation counts it when it gets to it
+                let counted = old_record
+                    .as_ref()
+                    .filter(|record| record.last_updated_epoch == pending.epoch_height);
+                match counted {
+                    Some(record) => {
+                        pending.tally.sub(&record.choice, record.stake.0);
+                        pending.tally.add(&vote, stake);
+                        pending.epoch_height
+                    }
+                    None => self.last_epoch_height,
+                }
+            }
+            None => {
+                if let Some(record) = &old_record {
+                    self.tally.sub(&record.choice, record.stake.0);
+                }
+                self.tally.add(&vote, stake);
+                self.last_epoch_height
+            }
+        };
+        self.votes.insert(
+            account_id.clone(),
+            VoteRecord {
+                choice: vote,
+                stake: stake.into(),
+                voter_account_id: Some(voter_account_id),
+                voted_at_ms: env::block_timestamp_ms(),
+                voted_epoch: env::epoch_height(),
+                last_updated_epoch,
+            },
+        );
+        if self.pending_ping.is_none() {
+            self.check_result(proposal_id, config);
+        }
+        // emit event
+        Event::Voted {
+            proposal_id: &proposal_id,
+            validator_id: &account_id,
+            vote: &vote,
+        }
+        .emit();
+    }
+
+    /// Record the end of the voting once the deadline has passed without an outcome. The tally
+    /// is kept as of the last completed `ping`, while the total stake is the one of the epoch the
+    /// proposal is finalized in, since the total stake of earlier epochs isn't available. A
+    /// rejection is also reported with `proposal_rejected`, like one decided by the votes.
+    pub fn finalize(&mut self, proposal_id: ProposalId, config: &Config) {
+        match self.result.as_ref().map(|result| result.outcome) {
+            None => {}
+            Some(Outcome::Cancelled) => env::panic_str("Proposal has been cancelled"),
+            Some(_) => env::panic_str("Voting has already ended"),
+        }
+        require!(
+            self.is_past_deadline(),
+            "Voting deadline has not passed yet"
+        );
+        let outcome = if self.reject_on_expiry {
+            Outcome::Rejected
+        } else {
+            Outcome::Expired
+        };
+        self.pending_ping = None;
+        let total_stake = U128::from(validator_total_stake());
+        self.set_result(outcome, config, total_stake.0);
+        Event::ProposalExpired {
+            proposal_id: &proposal_id,
+            proposal: &self.description,
+            outcome: &outcome,
+            expiration_timestamp_ms: &U64::from(env::block_timestamp_ms()),
+            deadline_timestamp_ms: &self.deadline_timestamp_ms.map(U64::from),
+            deadline_epoch_height: &self.deadline_epoch_height.map(U64::from),
+            tally: &self.tally,
+            total_stake: &total_stake,
+            num_votes: &U64::from(self.votes.len() as u64),
+        }
+        .emit();
+        if outcome == Outcome::Rejected {
+            Event::ProposalRejected {
+                proposal_id: &proposal_id,
+                proposal: &self.description,
+                rejection_timestamp_ms: &U64::from(env::block_timestamp_ms()),
+                deadline_timestamp_ms: &self.deadline_timestamp_ms.map(U64::from),
+                deadline_epoch_height: &self.deadline_epoch_height.map(U64::from),
+                voted_stake: &self.tally.no,
+                total_stake: &total_stake,
+                num_votes: &U64::from(self.votes.len() as u64),
+            }
+            .emit();
+        }
+    }
+
+    /// End the voting with the given outcome, and freeze the tally it was decided with. The tally
+    /// is labelled with the epoch of the last completed `ping`, which its stake comes from.
+    pub fn set_result(&mut self, outcome: Outcome, config: &Config, total_stake: Balance) {
+        let timestamp_ms = env::block_timestamp_ms();
+        self.result = Some(ProposalResult {
+            outcome,
+            timestamp_ms,
+        });
+        self.final_tally = Some(FinalTally {
+            outcome,
+            voted_stake: match outcome {
+                Outcome::Rejected => self.tally.no,
+                _ => self.tally.yes,
+            },
+            tally: self.tally.clone(),
+            total_stake: total_stake.into(),
+            threshold: config.threshold,
+            num_votes: self.votes.len() as u64,
+            epoch_height: self.last_epoch_height,
+            timestamp_ms,
+        });
+    }
+
+    /// Check whether the voting has ended.
+    fn check_result(&mut self, proposal_id: ProposalId, config: &Config) {
+        require!(
+            self.result.is_none(),
+            "check result is called after result is already set"
+        );
+        let total_stake = validator_total_stake();
+        if config.is_approved(&self.tally, total_stake) {
+            self.set_result(Outcome::Approved, config, total_stake);
+            Event::ProposalApproved {
+                proposal_id: &proposal_id,
+                proposal: &self.description,
+                approval_timestamp_ms: &U64::from(env::block_timestamp_ms()),
+                deadline_timestamp_ms: &self.deadline_timestamp_ms.map(U64::from),
+                deadline_epoch_height: &self.deadline_epoch_height.map(U64::from),
+                voted_stake: &self.tally.yes,
+                total_stake: &U128::from(total_stake),
+                num_votes: &U64::from(self.votes.len() as u64),
+                threshold: &config.threshold,
+                quorum: &config.quorum,
+            }
+            .emit();
+        } else if config.is_rejected(&self.tally, total_stake) {
+            self.set_result(Outcome::Rejected, config, total_stake);
+            Event::ProposalRejected {
+                proposal_id: &proposal_id,
+                proposal: &self.description,
+                rejection_timestamp_ms: &U64::from(env::block_timestamp_ms()),
+                deadline_timestamp_ms: &self.deadline_timestamp_ms.map(U64::from),
+                deadline_epoch_height: &self.deadline_epoch_height.map(U64::from),
+                voted_stake: &self.tally.no,
+                total_stake: &U128::from(total_stake),
+                num_votes: &U64::from(self.votes.len() as u64),
+            }
+            .emit();
+        }
+    }
+}
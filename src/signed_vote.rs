@@ -0,0 +1,263 @@
+use crate::*;
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::{borsh, serde_json, CurveType, PublicKey};
+
+/// Prefix of NEP-413 messages, `2^31 + 413`.
+const NEP413_TAG: u32 = (1 << 31) + 413;
+
+/// The message signed according to NEP-413.
+#[near(serializers = [borsh])]
+struct Nep413Payload {
+    message: String,
+    nonce: [u8; 32],
+    recipient: String,
+    callback_url: Option<String>,
+}
+
+/// A vote signed off-chain with a key of the staking pool owner. The JSON of the vote is signed
+/// as the `message` of NEP-413, with this contract as the `recipient` and `nonce` as the last 8
+/// bytes, in big endian, of the 32 bytes NEP-413 nonce.
+#[near(serializers = [json])]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignedVote {
+    pub proposal_id: ProposalId,
+    pub staking_pool_id: AccountId,
+    pub vote: Vote,
+    /// Must be greater than the nonce of the previous signed vote for the staking pool.
+    pub nonce: u64,
+    /// The signed vote can't be submitted after this timestamp.
+    pub expires_at_ms: Timestamp,
+}
+
+/// A key registered by the owner of a staking pool to sign votes for the pool.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug, PartialEq)]
+pub struct VoteSigner {
+    pub public_key: PublicKey,
+    /// Owner of the staking pool when the key was registered. Votes signed with the key are
+    /// rejected once the owner of the pool changes.
+    pub owner_id: AccountId,
+}
+
+/// Signed vote methods
+#[near]
+impl Contract {
+    /// Submit a vote signed off-chain by the staking pool owner. Anyone can relay it. `payload`
+    /// is the signed JSON of a `SignedVote`. The key must be the key of the implicit account that
+    /// owns the staking pool, or the key the owner registered with `set_vote_signer`. Access keys
+    /// of a named owner account can't be read by the contract, so such an owner registers the key
+    /// first.
+    pub fn submit_signed_vote(
+        &mut self,
+        payload: String,
+        public_key: PublicKey,
+        signature: Base64VecU8,
+    ) -> Promise {
+        let signed_vote: SignedVote = serde_json::from_str(&payload)
+            .unwrap_or_else(|_| env::panic_str("Invalid signed vote payload"));
+        self.assert_signed_vote_usable(&signed_vote);
+        require!(
+            validator_stake(&signed_vote.staking_pool_id) > 0,
+            format!("{} is not a validator", signed_vote.staking_pool_id)
+        );
+        require!(
+            public_key.curve_type() == CurveType::ED25519,
+            "Only ed25519 keys are supported"
+        );
+        let signature: [u8; 64] = signature
+            .0
+            .try_into()
+            .unwrap_or_else(|_| env::panic_str("Invalid signature length"));
+        let message = Nep413Payload {
+            message: payload,
+            nonce: nep413_nonce(signed_vote.nonce),
+            recipient: env::current_account_id().to_string(),
+            callback_url: None,
+        };
+        let mut bytes = borsh::to_vec(&NEP413_TAG).unwrap();
+        bytes.extend(borsh::to_vec(&message).unwrap());
+        require!(
+            env::ed25519_verify(
+                &signature,
+                &env::sha256(&bytes),
+                public_key.as_bytes()[1..].try_into().unwrap()
+            ),
+            "Invalid signature"
+        );
+
+        ext_staking_pool::ext(signed_vote.staking_pool_id.clone())
+            .with_static_gas(GET_OWNER_ID_GAS)
+            .get_owner_id()
+            .then(
+                Self::ext(env::current_account_id())
+                    .on_get_pool_owner_id_for_signed_vote(signed_vote, public_key),
+            )
+    }
+
+    /// Check the key belongs to the pool owner and vote.
+    #[private]
+    pub fn on_get_pool_owner_id_for_signed_vote(
+        &mut self,
+        signed_vote: SignedVote,
+        public_key: PublicKey,
+        #[callback_result] pool_owner_id_result: Result<AccountId, PromiseError>,
+    ) {
+        let Ok(actual_owner_id) = pool_owner_id_result else {
+            env::panic_str("Failed to get the staking pool owner id");
+        };
+        // another submission of the same vote may have been processed in the meantime
+        self.assert_signed_vote_usable(&signed_vote);
+        require!(
+            self.is_allowed_signer(&signed_vote.staking_pool_id, &actual_owner_id, &public_key),
+            "The key is not allowed to sign votes for the staking pool"
+        );
+        self.signed_vote_nonces
+            .insert(signed_vote.staking_pool_id.clone(), signed_vote.nonce);
+        self.internal_vote(
+            signed_vote.proposal_id,
+            signed_vote.vote,
+            signed_vote.staking_pool_id,
+            actual_owner_id,
+            DEFAULT_PING_LIMIT,
+        );
+    }
+
+    /// Register the key that signs votes for the staking pool, replacing the previous one.
+    /// The method is called by the staking pool owner. Only validators can register a key, which
+    /// bounds the storage paid by the voting contract to one entry per validator.
+    pub fn set_vote_signer(
+        &mut self,
+        staking_pool_id: AccountId,
+        public_key: PublicKey,
+    ) -> Promise {
+        require!(
+            validator_stake(&staking_pool_id) > 0,
+            format!("{} is not a validator", staking_pool_id)
+        );
+        require!(
+            public_key.curve_type() == CurveType::ED25519,
+            "Only ed25519 keys are supported"
+        );
+        self.internal_check_pool_owner_for_signer(staking_pool_id, Some(public_key))
+    }
+
+    /// Revoke the key that signs votes for the staking pool. The method is called by the staking
+    /// pool owner.
+    pub fn remove_vote_signer(&mut self, staking_pool_id: AccountId) -> Promise {
+        self.internal_check_pool_owner_for_signer(staking_pool_id, None)
+    }
+
+    /// Check the pool owner id and update the vote signer of the staking pool.
+    #[private]
+    pub fn on_get_pool_owner_id_for_vote_signer(
+        &mut self,
+        pool_owner_id: AccountId,
+        staking_pool_id: AccountId,
+        public_key: Option<PublicKey>,
+        #[callback_result] pool_owner_id_result: Result<AccountId, PromiseError>,
+    ) {
+        let Ok(actual_owner_id) = pool_owner_id_result else {
+            env::panic_str("Failed to get the staking pool owner id");
+        };
+        require!(
+            pool_owner_id == actual_owner_id,
+            "Only the staking pool owner can change its vote signer"
+        );
+        match public_key {
+            Some(public_key) => {
+                Event::VoteSignerSet {
+                    staking_pool_id: &staking_pool_id,
+                    owner_id: &pool_owner_id,
+                    public_key: &public_key,
+                }
+                .emit();
+                self.vote_signers.insert(
+                    staking_pool_id,
+                    VoteSigner {
+                        public_key,
+                        owner_id: pool_owner_id,
+                    },
+                );
+            }
+            None => {
+                let signer = self
+                    .vote_signers
+                    .remove(&staking_pool_id)
+                    .unwrap_or_else(|| env::panic_str("The staking pool has no vote signer"));
+                Event::VoteSignerRemoved {
+                    staking_pool_id: &staking_pool_id,
+                    owner_id: &pool_owner_id,
+                    public_key: &signer.public_key,
+                }
+                .emit();
+            }
+        }
+    }
+
+    /// Returns the key registered to sign votes for the staking pool, if any.
+    pub fn get_vote_signer(&self, staking_pool_id: AccountId) -> Option<VoteSigner> {
+        self.vote_signers.get(&staking_pool_id).cloned()
+    }
+
+    /// Returns the nonce of the last signed vote submitted for the staking pool, `0` if none.
+    pub fn get_signed_vote_nonce(&self, staking_pool_id: AccountId) -> u64 {
+        self.signed_vote_nonces
+            .get(&staking_pool_id)
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+impl Contract {
+    fn assert_signed_vote_usable(&self, signed_vote: &SignedVote) {
+        require!(
+            env::block_timestamp_ms() < signed_vote.expires_at_ms,
+            "Signed vote has expired"
+        );
+        require!(
+            signed_vote.nonce > self.get_signed_vote_nonce(signed_vote.staking_pool_id.clone()),
+            "Nonce of the signed vote has already been used"
+        );
+    }
+
+    fn internal_check_pool_owner_for_signer(
+        &mut self,
+        staking_pool_id: AccountId,
+        public_key: Option<PublicKey>,
+    ) -> Promise {
+        ext_staking_pool::ext(staking_pool_id.clone())
+            .with_static_gas(GET_OWNER_ID_GAS)
+            .get_owner_id()
+            .then(
+                Self::ext(env::current_account_id()).on_get_pool_owner_id_for_vote_signer(
+                    env::predecessor_account_id(),
+                    staking_pool_id,
+                    public_key,
+                ),
+            )
+    }
+
+    /// Whether the key can sign votes for the staking pool owned by `pool_owner_id`.
+    fn is_allowed_signer(
+        &self,
+        staking_pool_id: &AccountId,
+        pool_owner_id: &AccountId,
+        public_key: &PublicKey,
+    ) -> bool {
+        // the key of an implicit account is its account id
+        pool_owner_id.as_str() == hex_encode(&public_key.as_bytes()[1..])
+            || self
+                .vote_signers
+                .get(staking_pool_id)
+                .is_some_and(|signer| {
+                    &signer.public_key == public_key && &signer.owner_id == pool_owner_id
+                })
+    }
+}
+
+/// Returns the NEP-413 nonce of a signed vote, the nonce in big endian in the last 8 bytes.
+pub fn nep413_nonce(nonce: u64) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    bytes[24..].copy_from_slice(&nonce.to_be_bytes());
+    bytes
+}
@@ -0,0 +1,21 @@
+use crate::*;
+
+#[near]
+impl ValidatorVoting for Contract {
+    fn is_approved(&self, proposal_id: ProposalId) -> bool {
+        self.internal_get_proposal(proposal_id).status() == ProposalStatus::Approved
+    }
+
+    fn get_final_tally(&self, proposal_id: ProposalId) -> Option<FinalTally> {
+        self.internal_get_proposal(proposal_id).final_tally.clone()
+    }
+
+    fn voting_metadata(&self) -> VotingMetadata {
+        VotingMetadata {
+            standard: VOTING_STANDARD.to_string(),
+            version: VOTING_STANDARD_VERSION.to_string(),
+            threshold: self.config.threshold,
+            quorum: self.config.quorum,
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use crate::*;
+
+/// Maximum number of subscribers, which bounds the gas used to notify them.
+pub const MAX_SUBSCRIBERS: u32 = 5;
+const ON_PROPOSAL_FINALIZED_GAS: Gas = Gas::from_tgas(10);
+const ON_NOTIFY_SUBSCRIBER_GAS: Gas = Gas::from_tgas(3);
+/// Gas left for the rest of the call after the subscribers are notified.
+const NOTIFY_RESERVED_GAS: Gas = Gas::from_tgas(10);
+
+/// Subscriber methods
+#[near]
+impl Contract {
+    /// Add a contract to notify when a proposal is approved or rejected. Owner only.
+    pub fn add_subscriber(&mut self, subscriber_id: AccountId) {
+        self.assert_owner();
+        self.internal_add_subscriber(subscriber_id);
+    }
+
+    /// Stop notifying the contract. Owner only.
+    pub fn remove_subscriber(&mut self, subscriber_id: AccountId) {
+        self.assert_owner();
+        require!(
+            self.subscribers.remove(&subscriber_id),
+            "Subscriber not found"
+        );
+        Event::SubscriberRemoved {
+            subscriber_id: &subscriber_id,
+        }
+        .emit();
+    }
+
+    /// Notify the subscribers of a proposal whose notifications were deferred for lack of gas.
+    /// Can be called by anyone, once per deferred proposal. Requires 13 Tgas per subscriber and
+    /// 10 Tgas more for the call itself.
+    pub fn notify_subscribers(&mut self, proposal_id: ProposalId) {
+        require!(
+            self.pending_notifications.remove(&proposal_id),
+            "No deferred notifications for the proposal"
+        );
+        require!(
+            self.has_gas_to_notify(),
+            format!(
+                "Notifying the subscribers requires {} of gas",
+                self.notification_gas()
+            )
+        );
+        self.internal_send_notifications(proposal_id);
+    }
+
+    /// Log the failure to notify a subscriber. The failure doesn't affect the proposal.
+    #[private]
+    pub fn on_notify_subscriber(
+        &mut self,
+        proposal_id: ProposalId,
+        subscriber_id: AccountId,
+        #[callback_result] result: Result<(), PromiseError>,
+    ) {
+        if result.is_err() {
+            Event::SubscriberNotificationFailed {
+                proposal_id: &proposal_id,
+                subscriber_id: &subscriber_id,
+            }
+            .emit();
+        }
+    }
+
+    /// Returns the contracts notified when a proposal is approved or rejected.
+    pub fn get_subscribers(&self) -> Vec<AccountId> {
+        self.subscribers.iter().cloned().collect()
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_add_subscriber(&mut self, subscriber_id: AccountId) {
+        require!(
+            self.subscribers.len() < MAX_SUBSCRIBERS,
+            format!("Cannot have more than {} subscribers", MAX_SUBSCRIBERS)
+        );
+        require!(
+            self.subscribers.insert(subscriber_id.clone()),
+            "Subscriber already exists"
+        );
+        Event::SubscriberAdded {
+            subscriber_id: &subscriber_id,
+        }
+        .emit();
+    }
+
+    /// Notify the subscribers that the proposal was approved or rejected. If the remaining gas
+    /// can't cover the notifications, they are deferred to `notify_subscribers` instead, so the
+    /// vote or ping that ended the voting doesn't fail.
+    pub(crate) fn internal_notify_subscribers(&mut self, proposal_id: ProposalId) {
+        if self.subscribers.is_empty() {
+            return;
+        }
+        if !self.has_gas_to_notify() {
+            self.pending_notifications.insert(proposal_id);
+            Event::SubscriberNotificationDeferred {
+                proposal_id: &proposal_id,
+            }
+            .emit();
+            return;
+        }
+        self.internal_send_notifications(proposal_id);
+    }
+
+    /// Gas needed to notify all subscribers and finish the call.
+    fn notification_gas(&self) -> Gas {
+        ON_PROPOSAL_FINALIZED_GAS
+            .saturating_add(ON_NOTIFY_SUBSCRIBER_GAS)
+            .saturating_mul(self.subscribers.len() as u64)
+            .saturating_add(NOTIFY_RESERVED_GAS)
+    }
+
+    fn has_gas_to_notify(&self) -> bool {
+        env::prepaid_gas().saturating_sub(env::used_gas()) >= self.notification_gas()
+    }
+
+    /// Call `on_proposal_finalized` on each subscriber with the final tally of the proposal. Each
+    /// call is independent, and a failing subscriber is only logged.
+    fn internal_send_notifications(&self, proposal_id: ProposalId) {
+        let final_tally = self
+            .internal_get_proposal(proposal_id)
+            .final_tally
+            .clone()
+            .unwrap_or_else(|| env::panic_str("Voting of the proposal has not ended"));
+        let status = ProposalStatus::from(final_tally.outcome);
+        for subscriber_id in self.subscribers.iter() {
+            ext_subscriber::ext(subscriber_id.clone())
+                .with_static_gas(ON_PROPOSAL_FINALIZED_GAS)
+                .on_proposal_finalized(
+                    proposal_id,
+                    status,
+                    final_tally.voted_stake,
+                    final_tally.total_stake,
+                )
+                .then(
+                    Self::ext(env::current_account_id())
+                        .with_static_gas(ON_NOTIFY_SUBSCRIBER_GAS)
+                        .on_notify_subscriber(proposal_id, subscriber_id.clone()),
+                );
+        }
+    }
+}
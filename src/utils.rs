@@ -68,3 +68,13 @@ pub fn validator_total_stake() -> Balance {
     #[cfg(not(feature = "test"))]
     near_sdk::env::validator_total_stake().as_yoctonear()
 }
+
+/// Encodes the bytes as a lowercase hex string.
+pub fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Whether the string is a hex encoded sha256 hash.
+pub fn is_sha256_hex(hash: &str) -> bool {
+    hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit())
+}
@@ -9,10 +9,19 @@ type Balance = u128;
 
 const VOTE_GAS: Gas = Gas::from_tgas(100);
 
+/// Same as `Vote` of the voting contract.
+#[near(serializers = [json])]
+#[serde(rename_all = "lowercase")]
+pub enum Vote {
+    Yes,
+    No,
+    Abstain,
+}
+
 #[allow(dead_code)]
 #[ext_contract(ext_voting)]
 trait VotingContract {
-    fn vote(&mut self, is_vote: bool);
+    fn vote(&mut self, proposal_id: u64, vote: Vote, staking_pool_id: AccountId);
 
     fn set_validator_stake(validator_account_id: AccountId, amount: U128);
 }
@@ -77,13 +86,14 @@ impl MockStakingPool {
     }
 
     /// Owner's method.
-    /// Calls `vote(is_vote)` on the given voting contract account ID on behalf of the pool.
-    pub fn vote(&mut self, voting_account_id: AccountId, is_vote: bool) -> Promise {
+    /// Calls `vote(proposal_id, vote, staking_pool_id)` on the given voting contract account ID
+    /// on behalf of the pool.
+    pub fn vote(&mut self, voting_account_id: AccountId, proposal_id: u64, vote: Vote) -> Promise {
         require!(self.voting_account_id == voting_account_id, "Voting account id mismatching");
         self.assert_owner();
         ext_voting::ext(voting_account_id)
             .with_static_gas(VOTE_GAS)
-            .vote(is_vote)
+            .vote(proposal_id, vote, env::current_account_id())
     }
 
     /// Owner's method.
@@ -0,0 +1,91 @@
+use near_sdk::json_types::U128;
+use near_sdk::{env, near, require, AccountId, Gas, PanicOnDefault, Promise};
+use validator_voting_interface::{
+    ext_validator_voting, FinalTally, ProposalId, ProposalStatus, ProposalSubscriber,
+};
+
+const VIEW_GAS: Gas = Gas::from_tgas(5);
+
+/// A notification received from the voting contract.
+#[near(serializers = [borsh, json])]
+#[derive(Clone)]
+pub struct Notification {
+    pub proposal_id: ProposalId,
+    pub status: ProposalStatus,
+    pub voted_stake: U128,
+    pub total_stake: U128,
+}
+
+/// A downstream contract that reads the outcome of proposals through the typed interface of the
+/// voting contract, and subscribes to it, without depending on the contract crate.
+#[near(contract_state)]
+#[derive(PanicOnDefault)]
+pub struct VotingConsumer {
+    voting_account_id: AccountId,
+    notifications: Vec<Notification>,
+}
+
+#[near]
+impl VotingConsumer {
+    #[init]
+    #[private]
+    pub fn new(voting_account_id: AccountId) -> Self {
+        Self {
+            voting_account_id,
+            notifications: vec![],
+        }
+    }
+
+    pub fn check_approved(&self, proposal_id: ProposalId) -> Promise {
+        ext_validator_voting::ext(self.voting_account_id.clone())
+            .with_static_gas(VIEW_GAS)
+            .is_approved(proposal_id)
+            .then(Self::ext(env::current_account_id()).on_is_approved())
+    }
+
+    #[private]
+    pub fn on_is_approved(&self, #[callback_unwrap] approved: bool) -> bool {
+        approved
+    }
+
+    pub fn check_final_tally(&self, proposal_id: ProposalId) -> Promise {
+        ext_validator_voting::ext(self.voting_account_id.clone())
+            .with_static_gas(VIEW_GAS)
+            .get_final_tally(proposal_id)
+            .then(Self::ext(env::current_account_id()).on_get_final_tally())
+    }
+
+    #[private]
+    pub fn on_get_final_tally(
+        &self,
+        #[callback_unwrap] final_tally: Option<FinalTally>,
+    ) -> Option<FinalTally> {
+        final_tally
+    }
+
+    pub fn get_notifications(&self) -> Vec<Notification> {
+        self.notifications.clone()
+    }
+}
+
+#[near]
+impl ProposalSubscriber for VotingConsumer {
+    fn on_proposal_finalized(
+        &mut self,
+        proposal_id: ProposalId,
+        status: ProposalStatus,
+        voted_stake: U128,
+        total_stake: U128,
+    ) {
+        require!(
+            env::predecessor_account_id() == self.voting_account_id,
+            "Only the voting contract can notify"
+        );
+        self.notifications.push(Notification {
+            proposal_id,
+            status,
+            voted_stake,
+            total_stake,
+        });
+    }
+}
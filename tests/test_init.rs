@@ -19,15 +19,41 @@ async fn test_initialization() -> Result<(), Box<dyn std::error::Error>> {
 
     let contract_deadline = contract
         .view("get_deadline_timestamp")
-        .args_json(json!({}))
+        .args_json(json!({"proposal_id": 0}))
         .await?;
     assert_eq!(
         contract_deadline.json::<u64>()?,
         init_args.deadline_timestamp_ms
     );
 
-    let contract_proposal = contract.view("get_proposal").args_json(json!({})).await?;
+    let contract_proposal = contract
+        .view("get_proposal")
+        .args_json(json!({"proposal_id": 0}))
+        .await?;
     assert_eq!(contract_proposal.json::<String>()?, init_args.proposal);
 
+    let contract_config = contract.view("get_config").args_json(json!({})).await?;
+    assert_eq!(
+        contract_config.json::<serde_json::Value>()?,
+        json!({
+            "threshold": {"numerator": 2, "denominator": 3},
+            "quorum": null,
+        })
+    );
+
+    let metadata = contract
+        .view("voting_metadata")
+        .args_json(json!({}))
+        .await?;
+    assert_eq!(
+        metadata.json::<serde_json::Value>()?,
+        json!({
+            "standard": "validator-voting",
+            "version": "1.0.0",
+            "threshold": {"numerator": 2, "denominator": 3},
+            "quorum": null,
+        })
+    );
+
     Ok(())
 }
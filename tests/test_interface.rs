@@ -0,0 +1,143 @@
+use near_sdk::{Gas, NearToken};
+use serde_json::{json, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+mod utils;
+use utils::*;
+
+#[tokio::test]
+async fn test_read_outcome_from_another_contract() -> Result<(), Box<dyn std::error::Error>> {
+    let (staking_pool_contract, voting_contract, sandbox, owner) = setup_env(None).await?;
+    let consumer_contract =
+        deploy_voting_consumer_contract(&sandbox, voting_contract.id().clone()).await?;
+
+    let outcome = consumer_contract
+        .call("check_approved")
+        .args_json(json!({"proposal_id": 0}))
+        .gas(Gas::from_tgas(50))
+        .transact()
+        .await?;
+    assert!(!outcome.json::<bool>()?);
+
+    let alice = create_account(&sandbox, "alice", 10000).await?;
+    let outcome = alice
+        .call(staking_pool_contract.id(), "deposit_and_stake")
+        .gas(Gas::from_tgas(250))
+        .deposit(NearToken::from_near(1000))
+        .transact()
+        .await?;
+    assert!(
+        outcome.is_success(),
+        "{:#?}",
+        outcome.into_result().unwrap_err()
+    );
+    let outcome = owner
+        .call(voting_contract.id(), "vote")
+        .args_json(json!({
+            "proposal_id": 0,
+            "vote": "yes",
+            "staking_pool_id": staking_pool_contract.id()
+        }))
+        .gas(Gas::from_tgas(200))
+        .transact()
+        .await?;
+    assert!(
+        outcome.is_success(),
+        "{:#?}",
+        outcome.into_result().unwrap_err()
+    );
+
+    let outcome = consumer_contract
+        .call("check_approved")
+        .args_json(json!({"proposal_id": 0}))
+        .gas(Gas::from_tgas(50))
+        .transact()
+        .await?;
+    assert!(outcome.json::<bool>()?);
+
+    let outcome = consumer_contract
+        .call("check_final_tally")
+        .args_json(json!({"proposal_id": 0}))
+        .gas(Gas::from_tgas(50))
+        .transact()
+        .await?;
+    let final_tally = outcome.json::<Value>()?;
+    assert_eq!(final_tally["outcome"], "approved");
+    assert_eq!(
+        final_tally["voted_stake"],
+        NearToken::from_near(1000).as_yoctonear().to_string()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_notify_another_contract() -> Result<(), Box<dyn std::error::Error>> {
+    let sandbox = near_workspaces::sandbox().await?;
+    let root = sandbox.root_account()?;
+    let deadline_timestamp_ms = (SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis()
+        + 10 * 60 * 1000) as u64;
+    let (voting_contract, _) =
+        deploy_voting_contract_with_owner(&sandbox, deadline_timestamp_ms, Some(root.id().clone()))
+            .await?;
+    let (staking_pool_contract, owner, _) =
+        deploy_mock_staking_pool_contract(&sandbox, voting_contract.id().clone()).await?;
+    let consumer_contract =
+        deploy_voting_consumer_contract(&sandbox, voting_contract.id().clone()).await?;
+
+    let outcome = root
+        .call(voting_contract.id(), "add_subscriber")
+        .args_json(json!({"subscriber_id": consumer_contract.id()}))
+        .transact()
+        .await?;
+    assert!(
+        outcome.is_success(),
+        "{:#?}",
+        outcome.into_result().unwrap_err()
+    );
+
+    let alice = create_account(&sandbox, "alice", 10000).await?;
+    let outcome = alice
+        .call(staking_pool_contract.id(), "deposit_and_stake")
+        .gas(Gas::from_tgas(250))
+        .deposit(NearToken::from_near(1000))
+        .transact()
+        .await?;
+    assert!(
+        outcome.is_success(),
+        "{:#?}",
+        outcome.into_result().unwrap_err()
+    );
+    let outcome = owner
+        .call(voting_contract.id(), "vote")
+        .args_json(json!({
+            "proposal_id": 0,
+            "vote": "yes",
+            "staking_pool_id": staking_pool_contract.id()
+        }))
+        .gas(Gas::from_tgas(200))
+        .transact()
+        .await?;
+    assert!(
+        outcome.is_success(),
+        "{:#?}",
+        outcome.into_result().unwrap_err()
+    );
+
+    let notifications = consumer_contract
+        .view("get_notifications")
+        .await?
+        .json::<Vec<Value>>()?;
+    assert_eq!(notifications.len(), 1);
+    assert_eq!(notifications[0]["proposal_id"], 0);
+    assert_eq!(notifications[0]["status"], "approved");
+    assert_eq!(
+        notifications[0]["voted_stake"],
+        NearToken::from_near(1000).as_yoctonear().to_string()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,137 @@
+use near_sdk::{Gas, NearToken};
+use serde_json::{json, Value};
+
+mod utils;
+use utils::*;
+
+#[tokio::test]
+async fn test_vote_through_staking_pool() -> Result<(), Box<dyn std::error::Error>> {
+    let (staking_pool_contract, voting_contract, sandbox, owner) = setup_env(None).await?;
+
+    let alice = create_account(&sandbox, "alice", 10000).await?;
+    let outcome = alice
+        .call(staking_pool_contract.id(), "deposit_and_stake")
+        .gas(Gas::from_tgas(250))
+        .deposit(NearToken::from_near(1000))
+        .transact()
+        .await?;
+    assert!(
+        outcome.is_success(),
+        "{:#?}",
+        outcome.into_result().unwrap_err()
+    );
+
+    let outcome = owner
+        .call(staking_pool_contract.id(), "vote")
+        .args_json(json!({
+            "voting_account_id": voting_contract.id(),
+            "proposal_id": 0,
+            "vote": "yes"
+        }))
+        .gas(Gas::from_tgas(200))
+        .transact()
+        .await?;
+    assert!(
+        outcome.is_success(),
+        "{:#?}",
+        outcome.into_result().unwrap_err()
+    );
+
+    let vote = owner
+        .view(voting_contract.id(), "get_vote")
+        .args_json(json!({
+            "proposal_id": 0,
+            "account_id": staking_pool_contract.id()
+        }))
+        .await?;
+    let vote = vote.json::<Value>()?;
+    assert_eq!(vote["choice"], "yes");
+    assert_eq!(
+        vote["stake"],
+        NearToken::from_near(1000).as_yoctonear().to_string()
+    );
+    assert_eq!(
+        vote["voter_account_id"],
+        staking_pool_contract.id().as_str()
+    );
+
+    let status = owner
+        .view(voting_contract.id(), "get_status")
+        .args_json(json!({"proposal_id": 0}))
+        .await?;
+    assert_eq!(status.json::<String>()?, "approved");
+
+    let is_approved = owner
+        .view(voting_contract.id(), "is_approved")
+        .args_json(json!({"proposal_id": 0}))
+        .await?;
+    assert!(is_approved.json::<bool>()?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_non_validator_pool_cannot_vote() -> Result<(), Box<dyn std::error::Error>> {
+    let (staking_pool_contract, voting_contract, _sandbox, owner) = setup_env(None).await?;
+
+    // nothing is staked in the pool
+    let outcome = owner
+        .call(staking_pool_contract.id(), "vote")
+        .args_json(json!({
+            "voting_account_id": voting_contract.id(),
+            "proposal_id": 0,
+            "vote": "no"
+        }))
+        .gas(Gas::from_tgas(200))
+        .transact()
+        .await?;
+    assert!(
+        outcome.is_failure(),
+        "{:#?}",
+        outcome.into_result().unwrap_err()
+    );
+
+    let num_votes = owner
+        .view(voting_contract.id(), "get_num_votes")
+        .args_json(json!({"proposal_id": 0}))
+        .await?;
+    assert_eq!(num_votes.json::<u64>()?, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_only_pool_owner_can_vote_through_pool() -> Result<(), Box<dyn std::error::Error>> {
+    let (staking_pool_contract, voting_contract, sandbox, _owner) = setup_env(None).await?;
+
+    let alice = create_account(&sandbox, "alice", 10000).await?;
+    let outcome = alice
+        .call(staking_pool_contract.id(), "deposit_and_stake")
+        .gas(Gas::from_tgas(250))
+        .deposit(NearToken::from_near(1000))
+        .transact()
+        .await?;
+    assert!(
+        outcome.is_success(),
+        "{:#?}",
+        outcome.into_result().unwrap_err()
+    );
+
+    let outcome = alice
+        .call(staking_pool_contract.id(), "vote")
+        .args_json(json!({
+            "voting_account_id": voting_contract.id(),
+            "proposal_id": 0,
+            "vote": "yes"
+        }))
+        .gas(Gas::from_tgas(200))
+        .transact()
+        .await?;
+    assert!(
+        outcome.is_failure(),
+        "{:#?}",
+        outcome.into_result().unwrap_err()
+    );
+
+    Ok(())
+}
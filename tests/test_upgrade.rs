@@ -0,0 +1,251 @@
+use near_sdk::{Gas, NearToken};
+use serde_json::{json, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+mod utils;
+use utils::*;
+
+#[tokio::test]
+async fn test_upgrade_keeps_votes() -> Result<(), Box<dyn std::error::Error>> {
+    let sandbox = near_workspaces::sandbox().await?;
+    let dao = create_account(&sandbox, "dao", 100).await?;
+    let (voting_contract, _) = deploy_voting_contract_with_owner(
+        &sandbox,
+        (SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis()
+            + 10 * 60 * 1000) as u64,
+        Some(dao.id().clone()),
+    )
+    .await?;
+    let (staking_pool_contract, owner, _) =
+        deploy_mock_staking_pool_contract(&sandbox, voting_contract.id().clone()).await?;
+
+    let alice = create_account(&sandbox, "alice", 10000).await?;
+    let outcome = alice
+        .call(staking_pool_contract.id(), "deposit_and_stake")
+        .gas(Gas::from_tgas(250))
+        .deposit(NearToken::from_near(1000))
+        .transact()
+        .await?;
+    assert!(
+        outcome.is_success(),
+        "{:#?}",
+        outcome.into_result().unwrap_err()
+    );
+
+    let outcome = owner
+        .call(voting_contract.id(), "vote")
+        .args_json(json!({
+            "proposal_id": 0,
+            "vote": "yes",
+            "staking_pool_id": staking_pool_contract.id()
+        }))
+        .gas(Gas::from_tgas(200))
+        .transact()
+        .await?;
+    assert!(
+        outcome.is_success(),
+        "{:#?}",
+        outcome.into_result().unwrap_err()
+    );
+    let result_before = voting_contract
+        .view("get_proposal_result")
+        .args_json(json!({"proposal_id": 0}))
+        .await?
+        .json::<Value>()?;
+    assert_eq!(result_before["outcome"], "approved");
+
+    // only the owner can upgrade
+    let contract_wasm = std::fs::read("tests/res/validator_voting.wasm")?;
+    let outcome = alice
+        .call(voting_contract.id(), "upgrade")
+        .args_borsh(contract_wasm.clone())
+        .gas(Gas::from_tgas(300))
+        .transact()
+        .await?;
+    assert!(outcome.is_failure());
+
+    let outcome = dao
+        .call(voting_contract.id(), "upgrade")
+        .args_borsh(contract_wasm)
+        .gas(Gas::from_tgas(300))
+        .transact()
+        .await?;
+    assert!(
+        outcome.is_success(),
+        "{:#?}",
+        outcome.into_result().unwrap_err()
+    );
+
+    let state_version = voting_contract
+        .view("get_state_version")
+        .args_json(json!({}))
+        .await?;
+    assert_eq!(state_version.json::<String>()?, "v2");
+
+    let vote = voting_contract
+        .view("get_vote")
+        .args_json(json!({
+            "proposal_id": 0,
+            "account_id": staking_pool_contract.id()
+        }))
+        .await?
+        .json::<Value>()?;
+    assert_eq!(vote["choice"], "yes");
+    assert_eq!(
+        vote["stake"],
+        NearToken::from_near(1000).as_yoctonear().to_string()
+    );
+
+    let result_after = voting_contract
+        .view("get_proposal_result")
+        .args_json(json!({"proposal_id": 0}))
+        .await?
+        .json::<Value>()?;
+    assert_eq!(result_after, result_before);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_migrate_from_single_proposal() -> Result<(), Box<dyn std::error::Error>> {
+    let sandbox = near_workspaces::sandbox().await?;
+    let dao = create_account(&sandbox, "dao", 100).await?;
+    let deadline_timestamp_ms = (SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis()
+        + 10 * 60 * 1000) as u64;
+    let voting_contract =
+        deploy_single_proposal_voting_contract(&sandbox, deadline_timestamp_ms).await?;
+    let (staking_pool_contracts, owner, _) =
+        deploy_mock_staking_pool_contracts(&sandbox, voting_contract.id().clone(), 2).await?;
+
+    let alice = create_account(&sandbox, "alice", 10000).await?;
+    for staking_pool_contract in staking_pool_contracts.iter() {
+        let outcome = alice
+            .call(staking_pool_contract.id(), "deposit_and_stake")
+            .gas(Gas::from_tgas(250))
+            .deposit(NearToken::from_near(1000))
+            .transact()
+            .await?;
+        assert!(
+            outcome.is_success(),
+            "{:#?}",
+            outcome.into_result().unwrap_err()
+        );
+    }
+
+    // vote with the single proposal version
+    let outcome = owner
+        .call(voting_contract.id(), "vote")
+        .args_json(json!({
+            "vote": "yes",
+            "staking_pool_id": staking_pool_contracts[0].id()
+        }))
+        .gas(Gas::from_tgas(200))
+        .transact()
+        .await?;
+    assert!(
+        outcome.is_success(),
+        "{:#?}",
+        outcome.into_result().unwrap_err()
+    );
+
+    let contract_wasm = std::fs::read("tests/res/validator_voting.wasm")?;
+    let voting_contract = voting_contract
+        .as_account()
+        .deploy(&contract_wasm)
+        .await?
+        .result;
+    let outcome = voting_contract
+        .call("migrate_from_single_proposal")
+        .args_json(json!({"owner_id": dao.id()}))
+        .gas(Gas::from_tgas(300))
+        .transact()
+        .await?;
+    assert!(
+        outcome.is_success(),
+        "{:#?}",
+        outcome.into_result().unwrap_err()
+    );
+
+    let owner_id = voting_contract
+        .view("get_owner_id")
+        .args_json(json!({}))
+        .await?
+        .json::<Option<String>>()?;
+    assert_eq!(owner_id.as_deref(), Some(dao.id().as_str()));
+    let description = voting_contract
+        .view("get_proposal")
+        .args_json(json!({"proposal_id": 0}))
+        .await?
+        .json::<String>()?;
+    assert_eq!(description, "test_proposal");
+    let deadline = voting_contract
+        .view("get_deadline_timestamp")
+        .args_json(json!({"proposal_id": 0}))
+        .await?
+        .json::<Option<u64>>()?;
+    assert_eq!(deadline, Some(deadline_timestamp_ms));
+
+    let vote = voting_contract
+        .view("get_vote")
+        .args_json(json!({
+            "proposal_id": 0,
+            "account_id": staking_pool_contracts[0].id()
+        }))
+        .await?
+        .json::<Value>()?;
+    assert_eq!(vote["choice"], "yes");
+    assert_eq!(
+        vote["stake"],
+        NearToken::from_near(1000).as_yoctonear().to_string()
+    );
+    let tally = voting_contract
+        .view("get_tally")
+        .args_json(json!({"proposal_id": 0}))
+        .await?
+        .json::<Value>()?;
+    assert_eq!(
+        tally,
+        json!({
+            "yes": NearToken::from_near(1000).as_yoctonear().to_string(),
+            "no": "0",
+            "abstain": "0",
+        })
+    );
+    let status = voting_contract
+        .view("get_status")
+        .args_json(json!({"proposal_id": 0}))
+        .await?
+        .json::<String>()?;
+    assert_eq!(status, "active");
+
+    // the migrated proposal keeps counting votes
+    let outcome = owner
+        .call(voting_contract.id(), "vote")
+        .args_json(json!({
+            "proposal_id": 0,
+            "vote": "yes",
+            "staking_pool_id": staking_pool_contracts[1].id()
+        }))
+        .gas(Gas::from_tgas(200))
+        .transact()
+        .await?;
+    assert!(
+        outcome.is_success(),
+        "{:#?}",
+        outcome.into_result().unwrap_err()
+    );
+    let status = voting_contract
+        .view("get_status")
+        .args_json(json!({"proposal_id": 0}))
+        .await?
+        .json::<String>()?;
+    assert_eq!(status, "approved");
+
+    Ok(())
+}
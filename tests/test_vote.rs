@@ -1,5 +1,5 @@
 use near_sdk::{AccountId, Gas, NearToken};
-use serde_json::json;
+use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -22,7 +22,11 @@ async fn test_non_validator_cannot_vote() -> Result<(), Box<dyn std::error::Erro
     let user_account = sandbox.dev_create_account().await?;
     let outcome = user_account
         .call(contract.id(), "vote")
-        .args_json(json!({"vote": "yes", "staking_pool_id": user_account.id()}))
+        .args_json(json!({
+            "proposal_id": 0,
+            "vote": "yes",
+            "staking_pool_id": user_account.id()
+        }))
         .transact()
         .await?;
     assert!(
@@ -34,7 +38,11 @@ async fn test_non_validator_cannot_vote() -> Result<(), Box<dyn std::error::Erro
     let user_account = sandbox.dev_create_account().await?;
     let outcome = user_account
         .call(contract.id(), "vote")
-        .args_json(json!({"vote": "no", "staking_pool_id": user_account.id()}))
+        .args_json(json!({
+            "proposal_id": 0,
+            "vote": "no",
+            "staking_pool_id": user_account.id()
+        }))
         .transact()
         .await?;
     assert!(
@@ -66,7 +74,11 @@ async fn test_only_pool_owner_can_vote() -> Result<(), Box<dyn std::error::Error
 
     let outcome = alice
         .call(voting_contract.id(), "vote")
-        .args_json(json!({"vote": "yes", "staking_pool_id": staking_pool_contract.id()}))
+        .args_json(json!({
+            "proposal_id": 0,
+            "vote": "yes",
+            "staking_pool_id": staking_pool_contract.id()
+        }))
         .transact()
         .await?;
     assert!(
@@ -77,7 +89,11 @@ async fn test_only_pool_owner_can_vote() -> Result<(), Box<dyn std::error::Error
 
     let outcome = alice
         .call(voting_contract.id(), "vote")
-        .args_json(json!({"vote": "no", "staking_pool_id": staking_pool_contract.id()}))
+        .args_json(json!({
+            "proposal_id": 0,
+            "vote": "no",
+            "staking_pool_id": staking_pool_contract.id()
+        }))
         .transact()
         .await?;
     assert!(
@@ -89,6 +105,107 @@ async fn test_only_pool_owner_can_vote() -> Result<(), Box<dyn std::error::Error
     Ok(())
 }
 
+#[tokio::test]
+async fn test_vote_by_voter() -> Result<(), Box<dyn std::error::Error>> {
+    let (staking_pool_contract, voting_contract, sandbox, owner) = setup_env(None).await?;
+
+    let alice = create_account(&sandbox, "alice", 10000).await?;
+    let outcome = alice
+        .call(staking_pool_contract.id(), "deposit_and_stake")
+        .args_json(json!({}))
+        .gas(Gas::from_tgas(250))
+        .deposit(NearToken::from_near(1000))
+        .transact()
+        .await?;
+    assert!(
+        outcome.is_success(),
+        "{:#?}",
+        outcome.into_result().unwrap_err()
+    );
+
+    // only the pool owner can register a voter
+    let outcome = alice
+        .call(voting_contract.id(), "set_voter")
+        .args_json(json!({
+            "staking_pool_id": staking_pool_contract.id(),
+            "voter_id": alice.id()
+        }))
+        .gas(Gas::from_tgas(100))
+        .transact()
+        .await?;
+    assert!(
+        outcome.is_failure(),
+        "{:#?}",
+        outcome.into_result().unwrap_err()
+    );
+
+    let outcome = owner
+        .call(voting_contract.id(), "set_voter")
+        .args_json(json!({
+            "staking_pool_id": staking_pool_contract.id(),
+            "voter_id": alice.id()
+        }))
+        .gas(Gas::from_tgas(100))
+        .transact()
+        .await?;
+    assert!(
+        outcome.is_success(),
+        "{:#?}",
+        outcome.into_result().unwrap_err()
+    );
+
+    let outcome = alice
+        .call(voting_contract.id(), "vote")
+        .args_json(json!({
+            "proposal_id": 0,
+            "vote": "yes",
+            "staking_pool_id": staking_pool_contract.id()
+        }))
+        .gas(Gas::from_tgas(200))
+        .transact()
+        .await?;
+    assert!(
+        outcome.is_success(),
+        "{:#?}",
+        outcome.into_result().unwrap_err()
+    );
+
+    let vote = owner
+        .view(voting_contract.id(), "get_vote")
+        .args_json(json!({
+            "proposal_id": 0,
+            "account_id": staking_pool_contract.id()
+        }))
+        .await?;
+    let vote = vote.json::<Value>()?;
+    assert_eq!(vote["choice"], "yes");
+    assert_eq!(vote["voter_account_id"], alice.id().as_str());
+
+    let outcome = owner
+        .call(voting_contract.id(), "remove_voter")
+        .args_json(json!({
+            "staking_pool_id": staking_pool_contract.id()
+        }))
+        .gas(Gas::from_tgas(100))
+        .transact()
+        .await?;
+    assert!(
+        outcome.is_success(),
+        "{:#?}",
+        outcome.into_result().unwrap_err()
+    );
+
+    let voter = owner
+        .view(voting_contract.id(), "get_voter")
+        .args_json(json!({
+            "staking_pool_id": staking_pool_contract.id()
+        }))
+        .await?;
+    assert!(voter.json::<Option<Value>>()?.is_none());
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_simple_vote() -> Result<(), Box<dyn std::error::Error>> {
     let (staking_pool_contract, voting_contract, sandbox, owner) = setup_env(None).await?;
@@ -117,6 +234,7 @@ async fn test_simple_vote() -> Result<(), Box<dyn std::error::Error>> {
     let outcome = owner
         .call(voting_contract.id(), "vote")
         .args_json(json!({
+            "proposal_id": 0,
             "vote": "yes",
             "staking_pool_id": staking_pool_contract.id()
         }))
@@ -167,6 +285,7 @@ async fn test_many_votes() -> Result<(), Box<dyn std::error::Error>> {
 
         let outcome = owner
             .call(voting_contract.id(), "ping")
+            .args_json(json!({"proposal_id": 0}))
             .gas(Gas::from_tgas(300))
             .transact()
             .await?;
@@ -188,6 +307,7 @@ async fn test_many_votes() -> Result<(), Box<dyn std::error::Error>> {
         let outcome = owner
             .call(voting_contract.id(), "vote")
             .args_json(json!({
+                "proposal_id": 0,
                 "vote": "yes",
                 "staking_pool_id": staking_pool_contract.id()
             }))
@@ -205,8 +325,9 @@ async fn test_many_votes() -> Result<(), Box<dyn std::error::Error>> {
             "Votes: {:#?}",
             voting_contract
                 .view("get_votes")
+                .args_json(json!({"proposal_id": 0}))
                 .await?
-                .json::<HashMap<AccountId, String>>()?
+                .json::<HashMap<AccountId, Value>>()?
         );
     }
 
@@ -250,6 +371,7 @@ async fn test_vote_expiration() -> Result<(), Box<dyn std::error::Error>> {
     let outcome = owner
         .call(voting_contract.id(), "vote")
         .args_json(json!({
+            "proposal_id": 0,
             "vote": "yes",
             "staking_pool_id": staking_pool_contract.id()
         }))
@@ -284,10 +406,11 @@ async fn test_vote_no_after_vote_yes() -> Result<(), Box<dyn std::error::Error>>
         outcome.into_result().unwrap_err()
     );
 
+    // keep the NO stake of the first pool below 1/3 of the total stake
     let outcome = alice
         .call(staking_pool_contracts[1].id(), "deposit_and_stake")
         .gas(Gas::from_tgas(250))
-        .deposit(NearToken::from_near(1000))
+        .deposit(NearToken::from_near(2500))
         .transact()
         .await?;
     assert!(
@@ -299,6 +422,7 @@ async fn test_vote_no_after_vote_yes() -> Result<(), Box<dyn std::error::Error>>
     let outcome = owner
         .call(voting_contract.id(), "vote")
         .args_json(json!({
+            "proposal_id": 0,
             "vote": "yes",
             "staking_pool_id": staking_pool_contracts[0].id()
         }))
@@ -312,12 +436,16 @@ async fn test_vote_no_after_vote_yes() -> Result<(), Box<dyn std::error::Error>>
         outcome.into_result().unwrap_err()
     );
 
-    let votes = owner.view(voting_contract.id(), "get_votes").await?;
-    assert_eq!(votes.json::<HashMap<AccountId, String>>()?.len(), 1);
+    let votes = owner
+        .view(voting_contract.id(), "get_votes")
+        .args_json(json!({"proposal_id": 0}))
+        .await?;
+    assert_eq!(votes.json::<HashMap<AccountId, Value>>()?.len(), 1);
 
     let outcome = owner
         .call(voting_contract.id(), "vote")
         .args_json(json!({
+            "proposal_id": 0,
             "vote": "no",
             "staking_pool_id": staking_pool_contracts[0].id()
         }))
@@ -331,8 +459,17 @@ async fn test_vote_no_after_vote_yes() -> Result<(), Box<dyn std::error::Error>>
         outcome.into_result().unwrap_err()
     );
 
-    let votes = owner.view(voting_contract.id(), "get_votes").await?;
-    assert_eq!(votes.json::<HashMap<AccountId, String>>()?.len(), 0);
+    let votes = owner
+        .view(voting_contract.id(), "get_votes")
+        .args_json(json!({"proposal_id": 0}))
+        .await?;
+    let votes = votes.json::<HashMap<AccountId, Value>>()?;
+    assert_eq!(votes.len(), 1);
+    assert_eq!(votes[staking_pool_contracts[0].id()]["choice"], "no");
+    assert_eq!(
+        votes[staking_pool_contracts[0].id()]["voter_account_id"],
+        owner.id().as_str()
+    );
 
     Ok(())
 }
@@ -355,10 +492,11 @@ async fn test_vote_yes_after_vote_no() -> Result<(), Box<dyn std::error::Error>>
         outcome.into_result().unwrap_err()
     );
 
+    // keep the NO stake of the first pool below 1/3 of the total stake
     let outcome = alice
         .call(staking_pool_contracts[1].id(), "deposit_and_stake")
         .gas(Gas::from_tgas(250))
-        .deposit(NearToken::from_near(1000))
+        .deposit(NearToken::from_near(2500))
         .transact()
         .await?;
     assert!(
@@ -370,6 +508,7 @@ async fn test_vote_yes_after_vote_no() -> Result<(), Box<dyn std::error::Error>>
     let outcome = owner
         .call(voting_contract.id(), "vote")
         .args_json(json!({
+            "proposal_id": 0,
             "vote": "no",
             "staking_pool_id": staking_pool_contracts[0].id()
         }))
@@ -383,12 +522,18 @@ async fn test_vote_yes_after_vote_no() -> Result<(), Box<dyn std::error::Error>>
         outcome.into_result().unwrap_err()
     );
 
-    let votes = owner.view(voting_contract.id(), "get_votes").await?;
-    assert_eq!(votes.json::<HashMap<AccountId, String>>()?.len(), 0);
+    let votes = owner
+        .view(voting_contract.id(), "get_votes")
+        .args_json(json!({"proposal_id": 0}))
+        .await?;
+    let votes = votes.json::<HashMap<AccountId, Value>>()?;
+    assert_eq!(votes.len(), 1);
+    assert_eq!(votes[staking_pool_contracts[0].id()]["choice"], "no");
 
     let outcome = owner
         .call(voting_contract.id(), "vote")
         .args_json(json!({
+            "proposal_id": 0,
             "vote": "yes",
             "staking_pool_id": staking_pool_contracts[0].id()
         }))
@@ -402,8 +547,13 @@ async fn test_vote_yes_after_vote_no() -> Result<(), Box<dyn std::error::Error>>
         outcome.into_result().unwrap_err()
     );
 
-    let votes = owner.view(voting_contract.id(), "get_votes").await?;
-    assert_eq!(votes.json::<HashMap<AccountId, String>>()?.len(), 1);
+    let votes = owner
+        .view(voting_contract.id(), "get_votes")
+        .args_json(json!({"proposal_id": 0}))
+        .await?;
+    let votes = votes.json::<HashMap<AccountId, Value>>()?;
+    assert_eq!(votes.len(), 1);
+    assert_eq!(votes[staking_pool_contracts[0].id()]["choice"], "yes");
 
     Ok(())
 }
@@ -441,6 +591,7 @@ async fn test_unstake_after_voting() -> Result<(), Box<dyn std::error::Error>> {
     let outcome = owner
         .call(voting_contract.id(), "vote")
         .args_json(json!({
+            "proposal_id": 0,
             "vote": "yes",
             "staking_pool_id": staking_pool_contracts[0].id()
         }))
@@ -468,8 +619,11 @@ async fn test_unstake_after_voting() -> Result<(), Box<dyn std::error::Error>> {
         outcome.into_result().unwrap_err()
     );
 
-    let votes = owner.view(voting_contract.id(), "get_votes").await?;
-    let votes = votes.json::<HashMap<AccountId, String>>()?;
+    let votes = owner
+        .view(voting_contract.id(), "get_votes")
+        .args_json(json!({"proposal_id": 0}))
+        .await?;
+    let votes = votes.json::<HashMap<AccountId, Value>>()?;
     assert_eq!(votes.len(), 1);
     assert!(votes.contains_key(staking_pool_contracts[0].id()));
 
@@ -478,6 +632,7 @@ async fn test_unstake_after_voting() -> Result<(), Box<dyn std::error::Error>> {
     let outcome = owner
         .call(voting_contract.id(), "vote")
         .args_json(json!({
+            "proposal_id": 0,
             "vote": "yes",
             "staking_pool_id": staking_pool_contracts[1].id()
         }))
@@ -491,10 +646,133 @@ async fn test_unstake_after_voting() -> Result<(), Box<dyn std::error::Error>> {
         outcome.into_result().unwrap_err()
     );
 
-    let votes = owner.view(voting_contract.id(), "get_votes").await?;
-    let votes = votes.json::<HashMap<AccountId, String>>()?;
+    let votes = owner
+        .view(voting_contract.id(), "get_votes")
+        .args_json(json!({"proposal_id": 0}))
+        .await?;
+    let votes = votes.json::<HashMap<AccountId, Value>>()?;
     assert_eq!(votes.len(), 2);
     assert!(votes.contains_key(staking_pool_contracts[1].id()));
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_vote_rejected() -> Result<(), Box<dyn std::error::Error>> {
+    let (staking_pool_contracts, voting_contract, sandbox, owner) = setup_env_many(2).await?;
+
+    let alice = create_account(&sandbox, "alice", 10000).await?;
+
+    for staking_pool_contract in staking_pool_contracts.iter() {
+        let outcome = alice
+            .call(staking_pool_contract.id(), "deposit_and_stake")
+            .gas(Gas::from_tgas(250))
+            .deposit(NearToken::from_near(1000))
+            .transact()
+            .await?;
+        assert!(
+            outcome.is_success(),
+            "{:#?}",
+            outcome.into_result().unwrap_err()
+        );
+    }
+
+    let outcome = owner
+        .call(voting_contract.id(), "vote")
+        .args_json(json!({
+            "proposal_id": 0,
+            "vote": "no",
+            "staking_pool_id": staking_pool_contracts[0].id()
+        }))
+        .gas(Gas::from_tgas(200))
+        .transact()
+        .await?;
+    assert!(
+        outcome.is_success(),
+        "{:#?}",
+        outcome.into_result().unwrap_err()
+    );
+
+    let result = voting_contract
+        .view("get_proposal_result")
+        .args_json(json!({"proposal_id": 0}))
+        .await?
+        .json::<Value>()?;
+    assert_eq!(result["outcome"], "rejected");
+
+    let outcome = owner
+        .call(voting_contract.id(), "vote")
+        .args_json(json!({
+            "proposal_id": 0,
+            "vote": "yes",
+            "staking_pool_id": staking_pool_contracts[1].id()
+        }))
+        .gas(Gas::from_tgas(200))
+        .transact()
+        .await?;
+    assert!(outcome
+        .into_result()
+        .unwrap_err()
+        .to_string()
+        .contains("Voting has already ended"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_vote_batch() -> Result<(), Box<dyn std::error::Error>> {
+    let (staking_pool_contracts, voting_contract, sandbox, owner) = setup_env_many(3).await?;
+
+    let alice = create_account(&sandbox, "alice", 10000).await?;
+
+    // the last pool has no stake
+    for staking_pool_contract in staking_pool_contracts.iter().take(2) {
+        let outcome = alice
+            .call(staking_pool_contract.id(), "deposit_and_stake")
+            .gas(Gas::from_tgas(250))
+            .deposit(NearToken::from_near(1000))
+            .transact()
+            .await?;
+        assert!(
+            outcome.is_success(),
+            "{:#?}",
+            outcome.into_result().unwrap_err()
+        );
+    }
+
+    let outcome = owner
+        .call(voting_contract.id(), "vote_batch")
+        .args_json(json!({
+            "proposal_id": 0,
+            "vote": "abstain",
+            "staking_pool_ids": staking_pool_contracts
+                .iter()
+                .map(|contract| contract.id())
+                .collect::<Vec<_>>()
+        }))
+        .gas(Gas::from_tgas(300))
+        .transact()
+        .await?;
+    assert!(
+        outcome.is_success(),
+        "{:#?}",
+        outcome.clone().into_result().unwrap_err()
+    );
+    let results = outcome.json::<Vec<Value>>()?;
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0]["error"], Value::Null);
+    assert_eq!(results[1]["error"], Value::Null);
+    assert_eq!(
+        results[2]["error"],
+        format!("{} is not a validator", staking_pool_contracts[2].id())
+    );
+
+    let num_votes = voting_contract
+        .view("get_num_votes")
+        .args_json(json!({"proposal_id": 0}))
+        .await?
+        .json::<u64>()?;
+    assert_eq!(num_votes, 2);
+
+    Ok(())
+}
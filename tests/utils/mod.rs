@@ -12,6 +12,7 @@ use std::{
 pub struct VotingInitArgs {
     pub proposal: String,
     pub deadline_timestamp_ms: u64,
+    pub owner_id: Option<AccountId>,
 }
 
 #[near(serializers = [json])]
@@ -24,6 +25,14 @@ pub struct MockStakingPoolInitArgs {
 pub async fn deploy_voting_contract(
     sandbox: &Worker<Sandbox>,
     deadline_timestamp_ms: u64,
+) -> Result<(Contract, VotingInitArgs), Box<dyn std::error::Error>> {
+    deploy_voting_contract_with_owner(sandbox, deadline_timestamp_ms, None).await
+}
+
+pub async fn deploy_voting_contract_with_owner(
+    sandbox: &Worker<Sandbox>,
+    deadline_timestamp_ms: u64,
+    owner_id: Option<AccountId>,
 ) -> Result<(Contract, VotingInitArgs), Box<dyn std::error::Error>> {
     let contract_wasm = std::fs::read("tests/res/validator_voting.wasm")?;
     let contract_account = create_account(sandbox, "voting", 100).await?;
@@ -33,6 +42,7 @@ pub async fn deploy_voting_contract(
     let init_args = VotingInitArgs {
         proposal: "test_proposal".to_string(),
         deadline_timestamp_ms,
+        owner_id,
     };
 
     let _ = contract
@@ -44,6 +54,27 @@ pub async fn deploy_voting_contract(
     Ok((contract, init_args))
 }
 
+/// Deploys the single proposal version of the contract, from before proposals had ids.
+pub async fn deploy_single_proposal_voting_contract(
+    sandbox: &Worker<Sandbox>,
+    deadline_timestamp_ms: u64,
+) -> Result<Contract, Box<dyn std::error::Error>> {
+    let contract_wasm = std::fs::read("tests/res/validator_voting_v0.wasm")?;
+    let contract_account = create_account(sandbox, "voting", 100).await?;
+    let contract = contract_account.deploy(&contract_wasm).await?.result;
+
+    let _ = contract
+        .call("new")
+        .args_json(json!({
+            "proposal": "test_proposal",
+            "deadline_timestamp_ms": deadline_timestamp_ms,
+        }))
+        .transact()
+        .await?;
+
+    Ok(contract)
+}
+
 pub async fn deploy_mock_staking_pool_contract(
     sandbox: &Worker<Sandbox>,
     voting_account_id: AccountId,
@@ -109,6 +140,23 @@ pub async fn deploy_mock_staking_pool_contracts(
     Ok((contracts, owner, init_args))
 }
 
+pub async fn deploy_voting_consumer_contract(
+    sandbox: &Worker<Sandbox>,
+    voting_account_id: AccountId,
+) -> Result<Contract, Box<dyn std::error::Error>> {
+    let contract_wasm =
+        near_workspaces::compile_project("./tests/contracts/voting-consumer").await?;
+    let contract_account = create_account(sandbox, "voting-consumer", 100).await?;
+    let contract = contract_account.deploy(&contract_wasm).await?.result;
+    let _ = contract
+        .call("new")
+        .args_json(json!({ "voting_account_id": voting_account_id }))
+        .transact()
+        .await?;
+
+    Ok(contract)
+}
+
 pub async fn setup_env(
     deadline_timestamp_ms: Option<u64>,
 ) -> Result<(Contract, Contract, Worker<Sandbox>, Account), Box<dyn std::error::Error>> {